@@ -1,38 +1,57 @@
 use raylib::prelude::*;
-use crate::matrix::create_view_matrix;
+use glam::{Mat4, Vec3A};
+use crate::matrix::{create_view_matrix, Transform};
 use std::f32::consts::PI;
 
+// Cuadros que tarda la mirada en girar hacia un objetivo nuevo
+const TURN_FRAMES: f32 = 30.0;
+
+// Giro en curso: orientación y distancia al objetivo al empezar
+#[derive(Clone, Copy)]
+struct Turn {
+    from: Transform,
+    distance: f32,
+    progress: f32,
+}
+
+#[derive(Clone)]
 pub struct Camera {
-    pub eye: Vector3,
-    pub target: Vector3,
-    pub up: Vector3,
+    pub eye: Vec3A,
+    pub target: Vec3A,
+    pub up: Vec3A,
     pub yaw: f32,
     pub pitch: f32,
     pub distance: f32,
     pub rotation_speed: f32,
+    turn: Option<Turn>,
 }
 
 impl Camera {
-    pub fn new(eye: Vector3, target: Vector3, up: Vector3) -> Self {
-        let direction = eye - target;
-        let distance = direction.length();
-
-        if distance == 0.0 {
+    pub fn new(eye: Vec3A, target: Vec3A, up: Vec3A) -> Self {
+        if eye == target {
             panic!("Camera eye and target must not be the same point");
         }
 
-        let pitch = (direction.y / distance).asin();
-        let yaw = direction.z.atan2(direction.x);
-
-        Self {
+        let mut camera = Self {
             eye,
             target,
             up,
-            yaw,
-            pitch,
-            distance,
+            yaw: 0.0,
+            pitch: 0.0,
+            distance: 0.0,
             rotation_speed: 0.05,
-        }
+            turn: None,
+        };
+        camera.aim_from_eye();
+        camera
+    }
+
+    // Ángulos y distancia que reproducen la posición actual del ojo respecto al objetivo
+    fn aim_from_eye(&mut self) {
+        let direction = self.eye - self.target;
+        self.distance = direction.length();
+        self.pitch = (direction.y / self.distance).asin();
+        self.yaw = direction.z.atan2(direction.x);
     }
 
     fn update_eye_position(&mut self) {
@@ -43,11 +62,48 @@ impl Camera {
         self.eye.z = self.target.z + self.distance * cos_pitch * self.yaw.sin();
     }
 
-    pub fn get_view_matrix(&self) -> Matrix {
+    // Empieza a girar la mirada hacia otro objetivo sin mover el ojo; los siguientes
+    // `set_target` lo completan
+    pub fn turn(&mut self) {
+        self.turn = Some(Turn {
+            from: Transform::looking_at(self.eye, self.target, self.up),
+            distance: self.distance,
+            progress: 0.0,
+        });
+    }
+
+    // Mueve el punto de mira conservando el ángulo y la distancia (cámara que sigue a un cuerpo).
+    // Durante un giro el ojo queda quieto: la orientación se interpola con slerp, así la
+    // velocidad angular es pareja, y al terminar la cámara sigue al objetivo desde ahí.
+    pub fn set_target(&mut self, target: Vec3A) {
+        let Some(mut turn) = self.turn else {
+            self.target = target;
+            self.update_eye_position();
+            return;
+        };
+
+        turn.progress = (turn.progress + 1.0 / TURN_FRAMES).min(1.0);
+        let t = turn.progress * turn.progress * (3.0 - 2.0 * turn.progress);
+        let pose = turn.from.lerp(&Transform::looking_at(self.eye, target, self.up), t);
+        let distance = turn.distance + ((target - self.eye).length() - turn.distance) * t;
+        self.target = self.eye + pose.transform_vector(Vec3A::Z) * distance;
+
+        if turn.progress < 1.0 {
+            self.turn = Some(turn);
+        } else {
+            self.turn = None;
+            self.aim_from_eye();
+        }
+    }
+
+    pub fn get_view_matrix(&self) -> Mat4 {
         create_view_matrix(self.eye, self.target, self.up)
     }
 
     pub fn process_input(&mut self, window: &RaylibHandle) {
+        if self.turn.is_some() {
+            return;
+        }
         let mut changed = false;
 
         if window.is_key_down(KeyboardKey::KEY_LEFT) {
@@ -71,4 +127,32 @@ impl Camera {
             self.update_eye_position();
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turn_keeps_the_eye_and_ends_on_the_target() {
+        let eye = Vec3A::new(0.0, 2.0, 10.0);
+        let mut camera = Camera::new(eye, Vec3A::ZERO, Vec3A::new(0.0, 1.0, 0.0));
+        let body = Vec3A::new(6.0, 0.0, 4.0);
+        camera.turn();
+
+        let mut previous = -1.0;
+        for frame in 0..TURN_FRAMES as usize {
+            camera.set_target(body);
+            assert_eq!(camera.eye, eye, "cuadro {frame}");
+            // La mirada avanza hacia el cuerpo sin pasarse
+            let progress = (camera.target - eye).normalize().dot((body - eye).normalize());
+            assert!(progress >= previous - 1e-5);
+            previous = progress;
+        }
+        assert!((camera.target - body).length() < 1e-3);
+        assert!((camera.distance - (eye - body).length()).abs() < 1e-3);
+
+        // Terminado el giro vuelve a seguirlo con el mismo ángulo
+        camera.set_target(body + Vec3A::new(1.0, 0.0, 0.0));
+        assert!((camera.eye - (eye + Vec3A::new(1.0, 0.0, 0.0))).length() < 1e-3);
+    }
+}
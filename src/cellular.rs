@@ -1,84 +1,172 @@
-pub struct CellularNoise {}
+use glam::Vec3A;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DistanceMetric {
+    #[default]
+    Euclidean,
+    Manhattan,
+    Chebyshev,
+}
+
+impl DistanceMetric {
+    fn distance(self, offset: Vec3A) -> f32 {
+        match self {
+            DistanceMetric::Euclidean => offset.length(),
+            DistanceMetric::Manhattan => offset.x.abs() + offset.y.abs() + offset.z.abs(),
+            DistanceMetric::Chebyshev => offset.x.abs().max(offset.y.abs()).max(offset.z.abs()),
+        }
+    }
+}
+
+// Distancias a los tres puntos característicos más cercanos y la celda ganadora
+#[derive(Debug, Clone, Copy)]
+pub struct CellularSample {
+    pub f1: f32,
+    pub f2: f32,
+    pub f3: f32,
+    pub cell_id: u32,
+    pub offset: Vec3A,  // posición menos el punto más cercano
+}
+
+impl CellularSample {
+    // F2 − F1: cero en los bordes entre celdas
+    pub fn edge(&self) -> f32 {
+        self.f2 - self.f1
+    }
+
+    // Valor estable en [0, 1] por celda, para colorear cada una por separado
+    pub fn cell_value(&self) -> f32 {
+        self.cell_id as f32 / u32::MAX as f32
+    }
+}
+
+// Ruido de Worley con semilla. `jitter` = 0 deja los puntos en el centro de cada celda
+// (rejilla regular) y 1 los reparte por toda la celda. Se buscan solo las celdas vecinas,
+// lo que es exacto para F1; F2 y F3 pueden quedar algo largos en casos raros.
+pub struct CellularNoise {
+    seed: u32,
+    pub metric: DistanceMetric,
+    pub jitter: f32,
+}
 
 impl CellularNoise {
-    pub fn new() -> Self {
-        CellularNoise {}
+    pub fn new(seed: u64) -> Self {
+        CellularNoise {
+            seed: (seed ^ (seed >> 32)) as u32,
+            metric: DistanceMetric::default(),
+            jitter: 1.0,
+        }
+    }
+
+    pub fn with_metric(mut self, metric: DistanceMetric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: f32) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    fn hash(&self, x: i32, y: i32, z: i32) -> u32 {
+        let mut h = self.seed;
+        h ^= (x as u32).wrapping_mul(0x8da6_b343);
+        h ^= (y as u32).wrapping_mul(0xd816_3841);
+        h ^= (z as u32).wrapping_mul(0xcb1a_b31f);
+        mix(h)
+    }
+
+    fn feature_point(&self, id: u32) -> Vec3A {
+        let channel = |salt: u32| (mix(id ^ salt) & 0xFFFF) as f32 / 65535.0;
+        let jittered = |salt: u32| 0.5 + (channel(salt) - 0.5) * self.jitter;
+        Vec3A::new(jittered(0x68bc_21eb), jittered(0x02e5_be93), jittered(0x967a_889b))
     }
 
-    fn hash2d(x: i32, y: i32) -> (f32, f32) {
-        let mut h = x as u32;
-        h = h.wrapping_mul(374761393).wrapping_add(y as u32);
-        h ^= h >> 13;
-        h = h.wrapping_mul(1274126177);
-        h ^= h >> 16;
-        
-        let h2 = h.wrapping_mul(668265263);
-        
-        let fx = (h & 0xFFFF) as f32 / 65535.0;
-        let fy = (h2 & 0xFFFF) as f32 / 65535.0;
-        
-        (fx, fy)
+    pub fn sample2d(&self, x: f32, y: f32) -> CellularSample {
+        self.search(Vec3A::new(x, y, 0.0), 0)
     }
 
-    pub fn cellular2d(&self, x: f32, y: f32) -> f32 {
-        let xi = x.floor() as i32;
-        let yi = y.floor() as i32;
-        
-        let mut min_dist = f32::INFINITY;
-        
-        for dy in -1..=1 {
-            for dx in -1..=1 {
-                let cell_x = xi + dx;
-                let cell_y = yi + dy;
-                
-                let (rand_x, rand_y) = Self::hash2d(cell_x, cell_y);
-                
-                let point_x = cell_x as f32 + rand_x;
-                let point_y = cell_y as f32 + rand_y;
-                
-                let dist_x = x - point_x;
-                let dist_y = y - point_y;
-                let dist = (dist_x * dist_x + dist_y * dist_y).sqrt();
-                
-                if dist < min_dist {
-                    min_dist = dist;
+    pub fn sample3d(&self, p: Vec3A) -> CellularSample {
+        self.search(p, 1)
+    }
+
+    fn search(&self, p: Vec3A, depth: i32) -> CellularSample {
+        let cell = [p.x.floor() as i32, p.y.floor() as i32, p.z.floor() as i32];
+        let mut sample = CellularSample {
+            f1: f32::INFINITY,
+            f2: f32::INFINITY,
+            f3: f32::INFINITY,
+            cell_id: 0,
+            offset: Vec3A::ZERO,
+        };
+
+        for dz in -depth..=depth {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let (i, j, k) = (cell[0] + dx, cell[1] + dy, cell[2] + dz);
+                    let id = self.hash(i, j, k);
+
+                    let mut feature = Vec3A::new(i as f32, j as f32, k as f32) + self.feature_point(id);
+                    if depth == 0 {
+                        feature.z = 0.0;
+                    }
+
+                    let offset = p - feature;
+                    let distance = self.metric.distance(offset);
+
+                    if distance < sample.f1 {
+                        sample.f3 = sample.f2;
+                        sample.f2 = sample.f1;
+                        sample.f1 = distance;
+                        sample.cell_id = id;
+                        sample.offset = offset;
+                    } else if distance < sample.f2 {
+                        sample.f3 = sample.f2;
+                        sample.f2 = distance;
+                    } else if distance < sample.f3 {
+                        sample.f3 = distance;
+                    }
                 }
             }
         }
-        
-        min_dist
+
+        sample
     }
+}
 
-    pub fn cellular2d_f2_f1(&self, x: f32, y: f32) -> f32 {
-        let xi = x.floor() as i32;
-        let yi = y.floor() as i32;
-        
-        let mut min_dist1 = f32::INFINITY;
-        let mut min_dist2 = f32::INFINITY;
-        
-        for dy in -1..=1 {
-            for dx in -1..=1 {
-                let cell_x = xi + dx;
-                let cell_y = yi + dy;
-                
-                let (rand_x, rand_y) = Self::hash2d(cell_x, cell_y);
-                
-                let point_x = cell_x as f32 + rand_x;
-                let point_y = cell_y as f32 + rand_y;
-                
-                let dist_x = x - point_x;
-                let dist_y = y - point_y;
-                let dist = (dist_x * dist_x + dist_y * dist_y).sqrt();
-                
-                if dist < min_dist1 {
-                    min_dist2 = min_dist1;
-                    min_dist1 = dist;
-                } else if dist < min_dist2 {
-                    min_dist2 = dist;
-                }
+// Finalizador de murmur3
+fn mix(mut h: u32) -> u32 {
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^ (h >> 16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances_are_sorted() {
+        for metric in [DistanceMetric::Euclidean, DistanceMetric::Manhattan, DistanceMetric::Chebyshev] {
+            let noise = CellularNoise::new(3).with_metric(metric);
+            for i in 0..200 {
+                let t = i as f32 * 0.31;
+                let sample = noise.sample3d(Vec3A::new(t.sin() * 5.0, t * 0.2, t.cos() * 5.0));
+                assert!(sample.f1 <= sample.f2 && sample.f2 <= sample.f3, "{metric:?}: {sample:?}");
             }
         }
-        
-        min_dist2 - min_dist1
+    }
+
+    // Sin dispersión los puntos quedan en el centro de cada celda: una rejilla regular
+    #[test]
+    fn zero_jitter_is_a_regular_grid() {
+        let noise = CellularNoise::new(8).with_metric(DistanceMetric::Chebyshev).with_jitter(0.0);
+        let center = noise.sample2d(2.5, -3.5);
+        assert!(center.f1.abs() < 1e-5);
+        assert!((center.f2 - 1.0).abs() < 1e-5);
+        let border = noise.sample2d(3.0, -3.3);
+        assert!(border.edge().abs() < 1e-5);
     }
 }
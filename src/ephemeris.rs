@@ -0,0 +1,336 @@
+use crate::orbit::OrbitalElements;
+use glam::Vec3A;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Días julianos de la época J2000.0 (2000-01-01 12:00 TT) y de 1970-01-01 00:00 UTC
+const J2000: f64 = 2451545.0;
+const UNIX_EPOCH_JD: f64 = 2440587.5;
+const DAYS_PER_CENTURY: f64 = 36525.0;
+
+// Años en los que valen los elementos medios de la tabla
+pub const VALID_YEARS: (i32, i32) = (1800, 2050);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Planet {
+    Mercury,
+    Venus,
+    Earth,
+    Mars,
+    Jupiter,
+    Saturn,
+    Uranus,
+    Neptune,
+}
+
+impl Planet {
+    pub const ALL: [Planet; 8] = [
+        Planet::Mercury,
+        Planet::Venus,
+        Planet::Earth,
+        Planet::Mars,
+        Planet::Jupiter,
+        Planet::Saturn,
+        Planet::Uranus,
+        Planet::Neptune,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Planet::Mercury => "mercury",
+            Planet::Venus => "venus",
+            Planet::Earth => "earth",
+            Planet::Mars => "mars",
+            Planet::Jupiter => "jupiter",
+            Planet::Saturn => "saturn",
+            Planet::Uranus => "uranus",
+            Planet::Neptune => "neptune",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|planet| planet.name() == name)
+    }
+
+    // Elementos medios J2000 y su variación por siglo juliano: a (UA), e, I, L, ϖ y Ω (grados).
+    // Tabla 1 de Standish, "Keplerian Elements for Approximate Positions of the Major Planets"
+    // (JPL), ajustada a 1800–2050. La Tierra es el baricentro Tierra-Luna.
+    fn mean_elements(self) -> [(f64, f64); 6] {
+        match self {
+            Planet::Mercury => [
+                (0.38709927, 0.00000037),
+                (0.20563593, 0.00001906),
+                (7.00497902, -0.00594749),
+                (252.25032350, 149472.67411175),
+                (77.45779628, 0.16047689),
+                (48.33076593, -0.12534081),
+            ],
+            Planet::Venus => [
+                (0.72333566, 0.00000390),
+                (0.00677672, -0.00004107),
+                (3.39467605, -0.00078890),
+                (181.97909950, 58517.81538729),
+                (131.60246718, 0.00268329),
+                (76.67984255, -0.27769418),
+            ],
+            Planet::Earth => [
+                (1.00000261, 0.00000562),
+                (0.01671123, -0.00004392),
+                (-0.00001531, -0.01294668),
+                (100.46457166, 35999.37244981),
+                (102.93768193, 0.32327364),
+                (0.0, 0.0),
+            ],
+            Planet::Mars => [
+                (1.52371034, 0.00001847),
+                (0.09339410, 0.00007882),
+                (1.84969142, -0.00813131),
+                (-4.55343205, 19140.30268499),
+                (-23.94362959, 0.44441088),
+                (49.55953891, -0.29257343),
+            ],
+            Planet::Jupiter => [
+                (5.20288700, -0.00011607),
+                (0.04838624, -0.00013253),
+                (1.30439695, -0.00183714),
+                (34.39644051, 3034.74612775),
+                (14.72847983, 0.21252668),
+                (100.47390909, 0.20469106),
+            ],
+            Planet::Saturn => [
+                (9.53667594, -0.00125060),
+                (0.05386179, -0.00050991),
+                (2.48599187, 0.00193609),
+                (49.95424423, 1222.49362201),
+                (92.59887831, -0.41897216),
+                (113.66242448, -0.28867794),
+            ],
+            Planet::Uranus => [
+                (19.18916464, -0.00196176),
+                (0.04725744, -0.00004397),
+                (0.77263783, -0.00242939),
+                (313.23810451, 428.48202785),
+                (170.95427630, 0.40805281),
+                (74.01692503, 0.04240589),
+            ],
+            Planet::Neptune => [
+                (30.06992276, 0.00026291),
+                (0.00859048, 0.00005105),
+                (1.77004347, 0.00035372),
+                (-55.12002969, 218.45945325),
+                (44.96476227, -0.32241464),
+                (131.78422574, -0.00508664),
+            ],
+        }
+    }
+
+    // Semieje mayor medio en J2000, en UA
+    pub fn semi_major_axis(self) -> f32 {
+        self.mean_elements()[0].0 as f32
+    }
+
+    // Elementos osculantes aproximados en la fecha (día juliano), en UA y radianes
+    pub fn elements(self, julian_day: f64) -> OrbitalElements {
+        let centuries = (julian_day - J2000) / DAYS_PER_CENTURY;
+        let [a, e, i, l, perihelion, node] = self.mean_elements().map(|(value, rate)| value + rate * centuries);
+
+        // ω = ϖ - Ω y M = L - ϖ; se reducen en f64 antes de pasar a f32
+        OrbitalElements {
+            semi_major_axis: a as f32,
+            eccentricity: e as f32,
+            inclination: (i as f32).to_radians(),
+            ascending_node: (node.rem_euclid(360.0) as f32).to_radians(),
+            argument_of_periapsis: ((perihelion - node).rem_euclid(360.0) as f32).to_radians(),
+            mean_anomaly: ((l - perihelion).rem_euclid(360.0) as f32).to_radians(),
+        }
+    }
+
+    // Posición heliocéntrica en UA, en los ejes de la escena (eclíptica J2000 en XZ)
+    pub fn heliocentric_position(self, julian_day: f64) -> Vec3A {
+        self.elements(julian_day).position()
+    }
+}
+
+// ====== Fechas ======
+
+// Días que avanza el calendario por unidad de tiempo de la escena: a 60 cuadros por segundo
+// son unos 24 días por segundo, y Mercurio da una vuelta en menos de cuatro
+pub const DAYS_PER_TIME_UNIT: f64 = 20.0;
+
+// Modo fecha: el día juliano `epoch` corresponde al instante `start` de la escena
+#[derive(Debug, Clone, Copy)]
+pub struct Calendar {
+    pub epoch: f64,
+    pub start: f32,
+}
+
+impl Calendar {
+    pub fn julian_day(&self, time: f32) -> f64 {
+        self.epoch + (time - self.start) as f64 * DAYS_PER_TIME_UNIT
+    }
+}
+
+// Día juliano de una fecha del calendario gregoriano en UTC (Meeus, cap. 7)
+pub fn julian_day(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> f64 {
+    let (year, month) = if month <= 2 { (year - 1, month + 12) } else { (year, month) };
+    let century = (year as f64 / 100.0).floor();
+    let gregorian = 2.0 - century + (century / 4.0).floor();
+    let fraction = (hour as f64 + minute as f64 / 60.0) / 24.0;
+    (365.25 * (year as f64 + 4716.0)).floor() + (30.6001 * (month as f64 + 1.0)).floor() + day as f64 + fraction + gregorian
+        - 1524.5
+}
+
+// Fecha y hora UTC de un día juliano, como (año, mes, día, hora, minuto)
+pub fn calendar_date(julian_day: f64) -> (i32, u32, u32, u32, u32) {
+    // Se redondea al minuto antes de separar el día para no mostrar "24:00"
+    let minutes = ((julian_day + 0.5) * 1440.0).round();
+    let z = (minutes / 1440.0).floor();
+    let minute_of_day = (minutes - z * 1440.0) as u32;
+
+    let alpha = ((z - 1867216.25) / 36524.25).floor();
+    let a = z + 1.0 + alpha - (alpha / 4.0).floor();
+    let b = a + 1524.0;
+    let c = ((b - 122.1) / 365.25).floor();
+    let d = (365.25 * c).floor();
+    let e = ((b - d) / 30.6001).floor();
+
+    let day = (b - d - (30.6001 * e).floor()) as u32;
+    let month = (if e < 14.0 { e - 1.0 } else { e - 13.0 }) as u32;
+    let year = (if month > 2 { c - 4716.0 } else { c - 4715.0 }) as i32;
+    (year, month, day, minute_of_day / 60, minute_of_day % 60)
+}
+
+pub fn format_date(julian_day: f64) -> String {
+    let (year, month, day, hour, minute) = calendar_date(julian_day);
+    format!("{year:04}-{month:02}-{day:02} {hour:02}:{minute:02} UTC")
+}
+
+// Acepta "AAAA-MM-DD", con hora opcional "AAAA-MM-DD HH:MM" (o con `T`), y "now"
+pub fn parse_date(text: &str) -> Result<f64, String> {
+    let text = text.trim();
+    if text == "now" {
+        return Ok(now());
+    }
+
+    let invalid = || format!("fecha inválida \"{text}\" (se espera AAAA-MM-DD o AAAA-MM-DD HH:MM)");
+    let (date, time) = match text.split_once(['T', ' ']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (text, None),
+    };
+
+    let mut fields = date.splitn(3, '-');
+    let mut field = || fields.next().and_then(|value| value.parse::<u32>().ok()).ok_or_else(invalid);
+    let (year, month, day) = (field()?, field()?, field()?);
+    let (hour, minute) = match time {
+        Some(time) => {
+            let (hour, minute) = time.split_once(':').ok_or_else(invalid)?;
+            (hour.parse::<u32>().map_err(|_| invalid())?, minute.parse::<u32>().map_err(|_| invalid())?)
+        }
+        None => (0, 0),
+    };
+
+    let year = year as i32;
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) || hour > 23 || minute > 59 {
+        return Err(invalid());
+    }
+    if !(VALID_YEARS.0..=VALID_YEARS.1).contains(&year) {
+        return Err(format!("{text}: las efemérides solo cubren {}–{}", VALID_YEARS.0, VALID_YEARS.1));
+    }
+
+    Ok(julian_day(year, month, day, hour, minute))
+}
+
+// Día juliano del reloj del sistema
+pub fn now() -> f64 {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0.0, |elapsed| elapsed.as_secs_f64());
+    UNIX_EPOCH_JD + seconds / 86400.0
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn julian_day_of_j2000() {
+        assert_eq!(julian_day(2000, 1, 1, 12, 0), 2451545.0);
+        assert_eq!(julian_day(1970, 1, 1, 0, 0), UNIX_EPOCH_JD);
+    }
+
+    #[test]
+    fn calendar_date_round_trip() {
+        let dates = [
+            (2000, 1, 1, 12, 0),
+            (2024, 2, 29, 23, 59),
+            (2024, 3, 1, 0, 0),
+            (1900, 2, 28, 6, 30),
+            (1999, 12, 31, 18, 45),
+            (2050, 12, 31, 0, 1),
+            (1800, 1, 1, 0, 0),
+        ];
+        for date in dates {
+            let (year, month, day, hour, minute) = date;
+            assert_eq!(calendar_date(julian_day(year, month, day, hour, minute)), date);
+        }
+    }
+
+    #[test]
+    fn calendar_date_rolls_over_months_and_leap_days() {
+        // Un día después del 28 de febrero depende del año bisiesto
+        assert_eq!(calendar_date(julian_day(2024, 2, 28, 12, 0) + 1.0), (2024, 2, 29, 12, 0));
+        assert_eq!(calendar_date(julian_day(2023, 2, 28, 12, 0) + 1.0), (2023, 3, 1, 12, 0));
+        assert_eq!(calendar_date(julian_day(1900, 2, 28, 12, 0) + 1.0), (1900, 3, 1, 12, 0));
+        assert_eq!(calendar_date(julian_day(2023, 12, 31, 23, 30) + 1.0 / 48.0), (2024, 1, 1, 0, 0));
+        // 23:59:50 se redondea al minuto sin mostrar "24:00"
+        assert_eq!(calendar_date(julian_day(2023, 4, 30, 23, 59) + 50.0 / 86400.0), (2023, 5, 1, 0, 0));
+    }
+
+    #[test]
+    fn parse_date_accepts_valid_dates() {
+        assert_eq!(parse_date("2000-01-01 12:00"), Ok(J2000));
+        assert_eq!(parse_date("2000-01-01T12:00"), Ok(J2000));
+        assert_eq!(parse_date("2024-02-29"), Ok(julian_day(2024, 2, 29, 0, 0)));
+    }
+
+    #[test]
+    fn parse_date_rejects_invalid_dates() {
+        for text in ["2023-02-29", "2024-13-01", "2024-04-31", "2024-01-01 24:00", "2024-01", "ayer"] {
+            assert!(parse_date(text).is_err(), "{text} debería ser inválida");
+        }
+    }
+
+    #[test]
+    fn parse_date_rejects_years_outside_the_table() {
+        assert!(parse_date(&format!("{}-12-31", VALID_YEARS.0 - 1)).is_err());
+        assert!(parse_date(&format!("{}-01-01", VALID_YEARS.1 + 1)).is_err());
+        assert!(parse_date(&format!("{}-01-01", VALID_YEARS.0)).is_ok());
+        assert!(parse_date(&format!("{}-12-31", VALID_YEARS.1)).is_ok());
+    }
+
+    #[test]
+    fn earth_is_near_perihelion_in_early_january() {
+        let perihelion = Planet::Earth.heliocentric_position(julian_day(2024, 1, 3, 0, 0)).length();
+        let aphelion = Planet::Earth.heliocentric_position(julian_day(2024, 7, 5, 0, 0)).length();
+        assert!((perihelion - 0.983).abs() < 0.002, "{perihelion}");
+        assert!((aphelion - 1.017).abs() < 0.002, "{aphelion}");
+    }
+
+    #[test]
+    fn planets_stay_near_their_mean_distance() {
+        for planet in Planet::ALL {
+            assert_eq!(Planet::from_name(planet.name()), Some(planet));
+            let elements = planet.elements(julian_day(2024, 6, 1, 0, 0));
+            let distance = planet.heliocentric_position(julian_day(2024, 6, 1, 0, 0)).length();
+            let a = planet.semi_major_axis();
+            assert!(distance >= a * (1.0 - elements.eccentricity) * 0.999, "{}", planet.name());
+            assert!(distance <= a * (1.0 + elements.eccentricity) * 1.001, "{}", planet.name());
+        }
+    }
+}
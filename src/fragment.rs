@@ -1,29 +1,112 @@
-use raylib::math::{Vector2, Vector3};
+use glam::{Vec2, Vec3A};
+use crate::varyings::{Varying, Varyings};
+use std::f32::consts::PI;
+
+// Dónde se calculan las UV esféricas: por vértice (interpoladas, con costura en ±π)
+// o por fragmento a partir de la posición en espacio objeto interpolada
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SphereUvMode {
+    PerVertex,
+    #[default]
+    PerFragment,
+}
+
+// Longitud y latitud de la dirección de `position`, normalizadas a [0, 1]
+pub fn spherical_uv(position: Vec3A) -> Vec2 {
+    let r = position.length();
+    if r <= 1e-3 {
+        return Vec2::ZERO;
+    }
+    let latitude = (position.y / r).clamp(-1.0, 1.0).asin();
+    let longitude = position.x.atan2(position.z);
+    Vec2::new((longitude + PI) / (2.0 * PI), (latitude + PI / 2.0) / PI)
+}
 
 #[derive(Clone)]
 pub struct Fragment {
-    pub position: Vector2,
-    pub world_position: Vector3,
-    pub color: Vector3,           // r = iluminación, g = u, b = v
+    pub position: Vec2,
+    pub world_position: Vec3A,
+    pub varyings: Varyings,       // interpolantes del vertex shader (UV, color, tangente...)
+    pub ddx: Varyings,            // variación de los interpolantes por píxel en x
+    pub ddy: Varyings,            // y en y (para elegir el mipmap)
     pub depth: f32,
-    pub normal: Vector3,          // ✅ nueva: normal interpolada y normalizada
+    pub normal: Vec3A,          // normal interpolada y normalizada
 }
 
 impl Fragment {
     pub fn new(
         x: f32,
         y: f32,
-        world_pos: Vector3,
-        color: Vector3,
+        world_pos: Vec3A,
+        varyings: Varyings,
         depth: f32,
-        normal: Vector3,          // ✅ añade normal aquí
+        normal: Vec3A,
     ) -> Self {
         Fragment {
-            position: Vector2::new(x, y),
+            position: Vec2::new(x, y),
             world_position: world_pos,
-            color,
+            varyings,
+            ddx: Varyings::new(),
+            ddy: Varyings::new(),
             depth,
             normal,
         }
     }
-}
\ No newline at end of file
+
+    pub fn with_derivatives(mut self, ddx: Varyings, ddy: Varyings) -> Self {
+        self.ddx = ddx;
+        self.ddy = ddy;
+        self
+    }
+
+    // Derivadas en pantalla de un interpolante de dos componentes. La u esférica es
+    // periódica: un salto de ~1 al cruzar la costura cuenta como un paso pequeño.
+    pub fn derivatives(&self, varying: Varying) -> (Vec2, Vec2) {
+        let mut ddx = self.ddx.vec2(varying);
+        let mut ddy = self.ddy.vec2(varying);
+        if varying == Varying::SphereUv {
+            ddx.x -= ddx.x.round();
+            ddy.x -= ddy.x.round();
+        }
+        (ddx, ddy)
+    }
+
+    // Recalcula la UV esférica (y sus derivadas) desde la posición en espacio objeto
+    pub fn compute_sphere_uv(&mut self) {
+        let position = self.varyings.vec3(Varying::ObjectPosition);
+        let uv = spherical_uv(position);
+        let uv_dx = spherical_uv(position + self.ddx.vec3(Varying::ObjectPosition)) - uv;
+        let uv_dy = spherical_uv(position + self.ddy.vec3(Varying::ObjectPosition)) - uv;
+
+        self.varyings.set_vec2(Varying::SphereUv, uv);
+        self.ddx.set_vec2(Varying::SphereUv, uv_dx);
+        self.ddy.set_vec2(Varying::SphereUv, uv_dy);
+    }
+
+    pub fn object_position(&self) -> Vec3A {
+        self.varyings.vec3(Varying::ObjectPosition)
+    }
+
+    pub fn sphere_uv(&self) -> Vec2 {
+        self.varyings.vec2(Varying::SphereUv)
+    }
+
+    pub fn vertex_color(&self) -> Vec3A {
+        self.varyings.vec3(Varying::Color)
+    }
+
+    // Muestra un interpolante como color: un float en gris, dos en rojo y verde, tres en RGB.
+    // Las direcciones y posiciones van de [-1, 1] a [0, 1].
+    pub fn debug_color(&self, varying: Varying) -> Vec3A {
+        let color = match self.varyings.get(varying) {
+            [v] => Vec3A::new(*v, *v, *v),
+            [x, y] => Vec3A::new(*x, *y, 0.0),
+            [x, y, z, ..] => Vec3A::new(*x, *y, *z),
+            [] => Vec3A::ZERO,
+        };
+        match varying {
+            Varying::Tangent | Varying::ObjectPosition => color * 0.5 + Vec3A::new(0.5, 0.5, 0.5),
+            _ => color,
+        }
+    }
+}
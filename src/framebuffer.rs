@@ -1,3 +1,4 @@
+use glam::Vec3A;
 use raylib::prelude::*;
 
 pub struct Framebuffer {
@@ -29,7 +30,7 @@ impl Framebuffer {
         self.depth_buffer.fill(f32::INFINITY);
     }
     
-    pub fn point(&mut self, x: i32, y: i32, depth: f32, color: Vector3) {
+    pub fn point(&mut self, x: i32, y: i32, depth: f32, color: Vec3A) {
         if x >= 0 && x < self.width && y >= 0 && y < self.height {
             let index = (y * self.width + x) as usize;
 
@@ -46,6 +47,28 @@ impl Framebuffer {
         }
     }
 
+    // Mezcla sobre el color existente sin escribir profundidad (superficies translúcidas)
+    pub fn blend_point(&mut self, x: i32, y: i32, depth: f32, color: Vec3A, alpha: f32) {
+        if x >= 0 && x < self.width && y >= 0 && y < self.height {
+            let index = (y * self.width + x) as usize;
+
+            if depth < self.depth_buffer[index] {
+                let existing = self.color_buffer.get_color(x, y);
+                let mix = |source: f32, destination: u8| {
+                    let destination = destination as f32 / 255.0;
+                    ((source.clamp(0.0, 1.0) * alpha + destination * (1.0 - alpha)) * 255.0) as u8
+                };
+                let pixel_color = Color::new(
+                    mix(color.x, existing.r),
+                    mix(color.y, existing.g),
+                    mix(color.z, existing.b),
+                    255,
+                );
+                self.color_buffer.draw_pixel(x, y, pixel_color);
+            }
+        }
+    }
+
     pub fn set_background_color(&mut self, color: Color) {
         self.background_color = color;
     }
@@ -54,11 +77,15 @@ impl Framebuffer {
         self.current_color = color;
     }
 
-    pub fn swap_buffers(&self, d: &mut RaylibHandle, thread: &RaylibThread) {
+    // `overlay` se dibuja como texto encima del cuadro (no queda en las grabaciones)
+    pub fn swap_buffers(&self, d: &mut RaylibHandle, thread: &RaylibThread, overlay: &[String]) {
         if let Ok(texture) = d.load_texture_from_image(thread, &self.color_buffer) {
             let mut d = d.begin_drawing(thread);
             d.clear_background(self.background_color);
             d.draw_texture(&texture, 0, 0, Color::WHITE);
+            for (line, text) in overlay.iter().enumerate() {
+                d.draw_text(text, 10, 10 + 22 * line as i32, 20, Color::LIGHTGRAY);
+            }
         }
     } 
 }
\ No newline at end of file
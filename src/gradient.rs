@@ -0,0 +1,163 @@
+use glam::Vec3A;
+use std::fs;
+
+// Espacio en el que se interpola entre paradas. Oklab mantiene la luminosidad percibida
+// pareja a lo largo de la rampa; los colores del renderizador se tratan como RGB lineal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorSpace {
+    Linear,
+    #[default]
+    Oklab,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ColorStop {
+    pub position: f32,
+    pub color: Vec3A,
+}
+
+// Gradiente de color con paradas ordenadas; fuera del rango se usan los extremos
+#[derive(Debug, Clone, Default)]
+pub struct ColorRamp {
+    stops: Vec<ColorStop>,
+    pub space: ColorSpace,
+}
+
+impl ColorRamp {
+    pub fn new(space: ColorSpace) -> Self {
+        ColorRamp { stops: Vec::new(), space }
+    }
+
+    pub fn with_stop(mut self, position: f32, color: Vec3A) -> Self {
+        self.add_stop(position, color);
+        self
+    }
+
+    pub fn add_stop(&mut self, position: f32, color: Vec3A) {
+        let index = self.stops.partition_point(|stop| stop.position <= position);
+        self.stops.insert(index, ColorStop { position, color });
+    }
+
+    // Formato de los archivos .ramp: una parada por línea "posición r g b", una línea
+    // opcional "space linear|oklab" y comentarios con '#'
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("No se pudo leer la paleta {path}: {err}"))?;
+        Self::parse(&text).map_err(|err| format!("{path}: {err}"))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut ramp = ColorRamp::default();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                ["space", "linear"] => ramp.space = ColorSpace::Linear,
+                ["space", "oklab"] => ramp.space = ColorSpace::Oklab,
+                [position, r, g, b] => {
+                    let values: Result<Vec<f32>, _> = [position, r, g, b].iter().map(|field| field.parse()).collect();
+                    let values = values.map_err(|_| format!("línea {}: número inválido", number + 1))?;
+                    ramp.add_stop(values[0], Vec3A::new(values[1], values[2], values[3]));
+                }
+                _ => return Err(format!("línea {}: se esperaba \"posición r g b\"", number + 1)),
+            }
+        }
+
+        if ramp.stops.is_empty() {
+            return Err("la paleta no tiene paradas".to_string());
+        }
+        Ok(ramp)
+    }
+
+    pub fn sample(&self, t: f32) -> Vec3A {
+        let (first, last) = match (self.stops.first(), self.stops.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Vec3A::ZERO,
+        };
+        if t <= first.position {
+            return first.color;
+        }
+        if t >= last.position {
+            return last.color;
+        }
+
+        let index = self.stops.partition_point(|stop| stop.position <= t);
+        let (a, b) = (self.stops[index - 1], self.stops[index]);
+        let span = b.position - a.position;
+        let f = if span > 0.0 { (t - a.position) / span } else { 1.0 };
+
+        match self.space {
+            ColorSpace::Linear => a.color + (b.color - a.color) * f,
+            ColorSpace::Oklab => {
+                let (la, lb) = (linear_to_oklab(a.color), linear_to_oklab(b.color));
+                oklab_to_linear(la + (lb - la) * f)
+            }
+        }
+    }
+}
+
+// Conversiones de Björn Ottosson entre RGB lineal y Oklab
+pub fn linear_to_oklab(c: Vec3A) -> Vec3A {
+    let l = (0.412_221_46 * c.x + 0.536_332_55 * c.y + 0.051_445_995 * c.z).cbrt();
+    let m = (0.211_903_5 * c.x + 0.680_699_5 * c.y + 0.107_396_96 * c.z).cbrt();
+    let s = (0.088_302_46 * c.x + 0.281_718_85 * c.y + 0.629_978_7 * c.z).cbrt();
+
+    Vec3A::new(
+        0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+        1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+        0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+    )
+}
+
+pub fn oklab_to_linear(c: Vec3A) -> Vec3A {
+    let l = c.x + 0.396_337_78 * c.y + 0.215_803_76 * c.z;
+    let m = c.x - 0.105_561_346 * c.y - 0.063_854_17 * c.z;
+    let s = c.x - 0.089_484_18 * c.y - 1.291_485_5 * c.z;
+    let (l, m, s) = (l * l * l, m * m * m, s * s * s);
+
+    Vec3A::new(
+        4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s,
+        -1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s,
+        -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sorts_stops_and_clamps_the_ends() {
+        let ramp = ColorRamp::parse("space linear\n# comentario\n1.0 1 1 1\n0.0 0 0 0  # negro\n").unwrap();
+        assert_eq!(ramp.space, ColorSpace::Linear);
+        assert_eq!(ramp.sample(-1.0), Vec3A::ZERO);
+        assert_eq!(ramp.sample(2.0), Vec3A::ONE);
+        assert!((ramp.sample(0.25) - Vec3A::ONE * 0.25).length() < 1e-6);
+    }
+
+    #[test]
+    fn parse_reports_the_line() {
+        assert_eq!(ColorRamp::parse("0 0 0 0\n0.5 1 x 1").unwrap_err(), "línea 2: número inválido");
+        assert_eq!(ColorRamp::parse("0 0 0").unwrap_err(), "línea 1: se esperaba \"posición r g b\"");
+        assert_eq!(ColorRamp::parse("space oklab").unwrap_err(), "la paleta no tiene paradas");
+    }
+
+    #[test]
+    fn oklab_round_trip() {
+        for color in [Vec3A::new(0.2, 0.5, 0.9), Vec3A::new(1.0, 0.0, 0.0), Vec3A::new(0.05, 0.05, 0.05)] {
+            assert!((oklab_to_linear(linear_to_oklab(color)) - color).length() < 1e-4, "{color:?}");
+        }
+    }
+
+    #[test]
+    fn builtin_palettes_load() {
+        for name in ["mars", "mocca", "saturn", "uranus"] {
+            let path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/palettes/").to_string() + name + ".ramp";
+            assert!(ColorRamp::load(&path).is_ok(), "{name}");
+        }
+    }
+}
@@ -1,11 +1,119 @@
-use raylib::prelude::*;
+use glam::Vec3A;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightKind {
+    Point { position: Vec3A },
+    Directional { direction: Vec3A },
+    Spot { position: Vec3A, direction: Vec3A, inner_angle: f32, outer_angle: f32 },
+    Ambient,
+}
+
+// Atenuación clásica 1 / (constante + lineal·d + cuadrática·d²)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Attenuation {
+    pub constant: f32,
+    pub linear: f32,
+    pub quadratic: f32,
+}
+
+impl Attenuation {
+    pub const NONE: Attenuation = Attenuation { constant: 1.0, linear: 0.0, quadratic: 0.0 };
+
+    // Cae a la mitad aproximadamente a la distancia `range`
+    pub fn with_range(range: f32) -> Self {
+        let range = range.max(1e-3);
+        Attenuation { constant: 1.0, linear: 0.5 / range, quadratic: 0.5 / (range * range) }
+    }
+
+    pub fn factor(&self, distance: f32) -> f32 {
+        let denominator = self.constant + self.linear * distance + self.quadratic * distance * distance;
+        if denominator > 0.0 { 1.0 / denominator } else { 1.0 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Light {
-    pub position: Vector3,
+    pub kind: LightKind,
+    pub color: Vec3A,
+    pub intensity: f32,
+    pub attenuation: Attenuation,
 }
 
 impl Light {
-    pub fn new(position: Vector3) -> Self {
-        Light { position }
+    pub fn point(position: Vec3A, color: Vec3A, intensity: f32) -> Self {
+        Light::new(LightKind::Point { position }, color, intensity)
+    }
+
+    pub fn directional(direction: Vec3A, color: Vec3A, intensity: f32) -> Self {
+        let direction = direction.normalize_or_zero();
+        Light::new(LightKind::Directional { direction }, color, intensity)
     }
-}
\ No newline at end of file
+
+    // Ángulos del cono en radianes; la escena los lee en grados
+    pub fn spot(
+        position: Vec3A,
+        direction: Vec3A,
+        inner_angle: f32,
+        outer_angle: f32,
+        color: Vec3A,
+        intensity: f32,
+    ) -> Self {
+        let direction = direction.normalize_or_zero();
+        Light::new(
+            LightKind::Spot { position, direction, inner_angle, outer_angle },
+            color,
+            intensity,
+        )
+    }
+
+    pub fn ambient(color: Vec3A, intensity: f32) -> Self {
+        Light::new(LightKind::Ambient, color, intensity)
+    }
+
+    fn new(kind: LightKind, color: Vec3A, intensity: f32) -> Self {
+        Light { kind, color, intensity, attenuation: Attenuation::NONE }
+    }
+
+    pub fn with_attenuation(mut self, attenuation: Attenuation) -> Self {
+        self.attenuation = attenuation;
+        self
+    }
+
+    pub fn is_ambient(&self) -> bool {
+        matches!(self.kind, LightKind::Ambient)
+    }
+
+    pub fn radiance(&self) -> Vec3A {
+        self.color * self.intensity
+    }
+
+    // Dirección hacia la luz y radiancia que llega a `point` (None para la ambiental)
+    pub fn incident(&self, point: Vec3A) -> Option<(Vec3A, Vec3A)> {
+        match self.kind {
+            LightKind::Ambient => None,
+            LightKind::Directional { direction } => Some((-direction, self.radiance())),
+            LightKind::Point { position } => {
+                let to_light = position - point;
+                let distance = to_light.length();
+                let falloff = self.attenuation.factor(distance);
+                Some((to_light.normalize_or_zero(), self.radiance() * falloff))
+            }
+            LightKind::Spot { position, direction, inner_angle, outer_angle } => {
+                let to_light = position - point;
+                let distance = to_light.length();
+                let light_dir = to_light.normalize_or_zero();
+
+                let cos_angle = (-light_dir).dot(direction);
+                let cos_inner = inner_angle.cos();
+                let cos_outer = outer_angle.cos();
+                let cone = ((cos_angle - cos_outer) / (cos_inner - cos_outer).max(1e-4)).clamp(0.0, 1.0);
+                if cone <= 0.0 {
+                    return None;
+                }
+
+                let falloff = self.attenuation.factor(distance) * cone * cone;
+                Some((light_dir, self.radiance() * falloff))
+            }
+        }
+    }
+}
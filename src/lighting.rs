@@ -0,0 +1,148 @@
+use crate::light::Light;
+use glam::Vec3A;
+use std::f32::consts::PI;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightingModel {
+    Unlit,
+    Lambert,
+    WrappedDiffuse { wrap: f32 },
+}
+
+impl Default for LightingModel {
+    fn default() -> Self {
+        LightingModel::WrappedDiffuse { wrap: 0.4 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SpecularModel {
+    #[default]
+    None,
+    // El color es el Ks del MTL; los materiales procedurales usan un gris
+    BlinnPhong { shininess: f32, color: Vec3A },
+    // Microfaceta GGX con Fresnel de Schlick (flujo metallic/roughness)
+    Ggx { metallic: f32, roughness: f32 },
+}
+
+pub struct Surface {
+    pub albedo: Vec3A,
+    pub position: Vec3A,
+    pub normal: Vec3A,
+}
+
+// La parte difusa multiplica al color de la superficie; la especular se suma
+pub struct LightContribution {
+    pub diffuse: Vec3A,
+    pub specular: Vec3A,
+}
+
+pub fn illuminate(
+    model: LightingModel,
+    specular_model: SpecularModel,
+    surface: &Surface,
+    lights: &[Light],
+    eye: Vec3A,
+) -> LightContribution {
+    let mut contribution = LightContribution {
+        diffuse: Vec3A::ZERO,
+        specular: Vec3A::ZERO,
+    };
+
+    if model == LightingModel::Unlit {
+        contribution.diffuse = Vec3A::ONE;
+        return contribution;
+    }
+
+    let normal = surface.normal;
+    let view_dir = (eye - surface.position).normalize_or_zero();
+
+    for light in lights {
+        if light.is_ambient() {
+            contribution.diffuse += light.radiance();
+            continue;
+        }
+
+        let Some((light_dir, radiance)) = light.incident(surface.position) else {
+            continue;
+        };
+        let n_dot_l = normal.dot(light_dir).max(0.0);
+
+        let diffuse = match model {
+            LightingModel::Unlit | LightingModel::Lambert => n_dot_l,
+            LightingModel::WrappedDiffuse { wrap } => (n_dot_l + wrap) / (1.0 + wrap),
+        };
+
+        let (diffuse_weight, specular) = if n_dot_l > 0.0 {
+            specular_term(specular_model, surface, light_dir, view_dir, n_dot_l)
+        } else {
+            (diffuse_weight(specular_model, surface.albedo, 1.0), Vec3A::ZERO)
+        };
+
+        contribution.diffuse += radiance * diffuse_weight * diffuse;
+        contribution.specular += radiance * specular;
+    }
+
+    contribution
+}
+
+// Devuelve el peso de la parte difusa (energía no reflejada) y el aporte especular
+fn specular_term(
+    model: SpecularModel,
+    surface: &Surface,
+    light_dir: Vec3A,
+    view_dir: Vec3A,
+    n_dot_l: f32,
+) -> (Vec3A, Vec3A) {
+    let normal = surface.normal;
+    let half_dir = (light_dir + view_dir).normalize_or_zero();
+    let n_dot_h = normal.dot(half_dir).max(0.0);
+
+    match model {
+        SpecularModel::None => (Vec3A::ONE, Vec3A::ZERO),
+        SpecularModel::BlinnPhong { shininess, color } => (Vec3A::ONE, color * n_dot_h.powf(shininess)),
+        SpecularModel::Ggx { metallic, roughness } => {
+            let roughness = roughness.clamp(0.04, 1.0);
+            let n_dot_v = normal.dot(view_dir).max(1e-4);
+            let v_dot_h = view_dir.dot(half_dir).max(0.0);
+
+            // Distribución normal GGX / Trowbridge-Reitz
+            let alpha = roughness * roughness;
+            let alpha2 = alpha * alpha;
+            let denom = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+            let distribution = alpha2 / (PI * denom * denom);
+
+            // Geometría de Smith con k de Schlick-GGX
+            let k = (roughness + 1.0) * (roughness + 1.0) / 8.0;
+            let geometry = (n_dot_l / (n_dot_l * (1.0 - k) + k)) * (n_dot_v / (n_dot_v * (1.0 - k) + k));
+
+            let fresnel = fresnel_schlick(f0(surface.albedo, metallic), v_dot_h);
+
+            // El difuso del proyecto no divide entre π, así que el BRDF especular se escala por π
+            let brdf = distribution * geometry / (4.0 * n_dot_l * n_dot_v);
+            let specular = fresnel * (brdf * n_dot_l * PI);
+            let diffuse_weight = (Vec3A::ONE - fresnel) * (1.0 - metallic);
+
+            (diffuse_weight, specular)
+        }
+    }
+}
+
+fn diffuse_weight(model: SpecularModel, albedo: Vec3A, cos_theta: f32) -> Vec3A {
+    match model {
+        SpecularModel::Ggx { metallic, .. } => {
+            (Vec3A::ONE - fresnel_schlick(f0(albedo, metallic), cos_theta)) * (1.0 - metallic)
+        }
+        _ => Vec3A::ONE,
+    }
+}
+
+fn f0(albedo: Vec3A, metallic: f32) -> Vec3A {
+    let dielectric = Vec3A::new(0.04, 0.04, 0.04);
+    dielectric + (albedo - dielectric) * metallic
+}
+
+fn fresnel_schlick(f0: Vec3A, cos_theta: f32) -> Vec3A {
+    let factor = (1.0 - cos_theta).clamp(0.0, 1.0).powi(5);
+    f0 + (Vec3A::ONE - f0) * factor
+}
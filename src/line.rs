@@ -0,0 +1,89 @@
+use crate::framebuffer::Framebuffer;
+use crate::Uniforms;
+use glam::{Vec3A, Vec4};
+
+// Distancia mínima delante de la cámara (igual al plano cercano de la proyección)
+const NEAR: f32 = 0.1;
+
+// Segmento 3D en coordenadas de mundo, con prueba de profundidad contra lo ya dibujado.
+// No escribe profundidad: las líneas se mezclan encima sin tapar superficies posteriores.
+pub fn line3d(framebuffer: &mut Framebuffer, uniforms: &Uniforms, from: Vec3A, to: Vec3A, color: Vec3A, alpha: f32) {
+    let to_clip = |p: Vec3A| uniforms.projection_matrix * (uniforms.view_matrix * p.extend(1.0));
+    let (mut a, mut b) = (to_clip(from), to_clip(to));
+
+    // Recorta contra el plano cercano (w = distancia delante de la cámara)
+    if a.w < NEAR && b.w < NEAR {
+        return;
+    }
+    if a.w < NEAR || b.w < NEAR {
+        let t = (NEAR - a.w) / (b.w - a.w);
+        let cut = a.lerp(b, t);
+        if a.w < NEAR { a = cut } else { b = cut }
+    }
+
+    let (Some(start), Some(end)) = (to_screen(uniforms, a), to_screen(uniforms, b)) else {
+        return;
+    };
+    let Some((start, end)) = clip_to_screen(start, end, framebuffer.width as f32, framebuffer.height as f32) else {
+        return;
+    };
+
+    let steps = (end.x - start.x).abs().max((end.y - start.y).abs()).ceil().max(1.0) as i32;
+    for step in 0..=steps {
+        let t = step as f32 / steps as f32;
+        let x = start.x + (end.x - start.x) * t;
+        let y = start.y + (end.y - start.y) * t;
+        let depth = start.z + (end.z - start.z) * t;
+        framebuffer.blend_point(x as i32, y as i32, depth, color, alpha);
+    }
+}
+
+// Recorre una lista de puntos; `closed` une el último con el primero
+pub fn polyline3d(framebuffer: &mut Framebuffer, uniforms: &Uniforms, points: &[Vec3A], closed: bool, color: Vec3A, alpha: f32) {
+    for pair in points.windows(2) {
+        line3d(framebuffer, uniforms, pair[0], pair[1], color, alpha);
+    }
+    if closed && points.len() > 2 {
+        line3d(framebuffer, uniforms, points[points.len() - 1], points[0], color, alpha);
+    }
+}
+
+fn to_screen(uniforms: &Uniforms, clip: Vec4) -> Option<Vec3A> {
+    if clip.w <= 0.0 {
+        return None;
+    }
+    let ndc = (clip / clip.w).truncate().extend(1.0);
+    Some(Vec3A::from((uniforms.viewport_matrix * ndc).truncate()))
+}
+
+// Liang–Barsky contra el rectángulo de la pantalla, para no recorrer píxeles invisibles
+fn clip_to_screen(start: Vec3A, end: Vec3A, width: f32, height: f32) -> Option<(Vec3A, Vec3A)> {
+    let delta = end - start;
+    let (mut t0, mut t1) = (0.0_f32, 1.0_f32);
+    let edges = [
+        (-delta.x, start.x),
+        (delta.x, width - 1.0 - start.x),
+        (-delta.y, start.y),
+        (delta.y, height - 1.0 - start.y),
+    ];
+
+    for (p, q) in edges {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+            continue;
+        }
+        let t = q / p;
+        if p < 0.0 {
+            t0 = t0.max(t);
+        } else {
+            t1 = t1.min(t);
+        }
+        if t0 > t1 {
+            return None;
+        }
+    }
+
+    Some((start + delta * t0, start + delta * t1))
+}
@@ -7,37 +7,72 @@ mod vertex;
 mod camera;
 mod shaders;
 mod light;
+mod lighting;
 mod simplex;
+mod noise;
 mod cellular;
+mod gradient;
+mod planet;
+mod material;
+mod varyings;
+mod texture;
+mod recorder;
+mod options;
+mod orbit;
+mod ephemeris;
+mod nbody;
+mod scene;
+mod scene_graph;
+mod line;
+mod profiler;
 
 use triangle::triangle;
-use obj_loader::Obj;
 use framebuffer::Framebuffer;
 use raylib::prelude::*;
 use std::thread;
 use std::time::Duration;
 use std::f32::consts::PI;
-use matrix::{create_model_matrix_y, create_projection_matrix, create_viewport_matrix};
+use glam::{Mat4, Vec3A};
+use matrix::{create_projection_matrix, create_viewport_matrix, normal_matrix, unproject};
 use vertex::Vertex;
-use camera::Camera;
-use shaders::{vertex_shader, fragment_shaders};
+use fragment::SphereUvMode;
+use varyings::Varying;
+use shaders::vertex_shader;
 use light::Light;
-use crate::shaders::ShaderType;
+use material::{Material, MaterialRegistry, shade};
+use scene::Scene;
+use line::{line3d, polyline3d};
+use std::process;
 use rand::Rng;
+use recorder::Recorder;
+use options::{Options, DEFAULT_RECORDING_PATH};
+use profiler::{Profiler, Stage};
+use ephemeris::format_date;
 
+// Tiempo de simulación que avanza cada cuadro a 60 FPS
+const TIME_STEP: f32 = 0.02;
+const FRAME_RATE: f32 = 60.0;
+const ORBIT_SEGMENTS: usize = 180;
+
+// Las matrices van en glam: el vertex shader y las líneas las aplican a cada vértice
 pub struct Uniforms {
-    pub model_matrix: Matrix,
-    pub view_matrix: Matrix,
-    pub projection_matrix: Matrix,
-    pub viewport_matrix: Matrix,
+    pub model_matrix: Mat4,
+    // Inversa traspuesta de la parte 3x3 del modelo, para las normales
+    pub normal_matrix: Mat4,
+    pub view_matrix: Mat4,
+    pub projection_matrix: Mat4,
+    pub viewport_matrix: Mat4,
+    pub camera_position: Vec3A,
+    pub sphere_uv_mode: SphereUvMode,
+    // Con `--varying` se pinta ese interpolante en lugar del material
+    pub debug_varying: Option<Varying>,
     pub is_ring: bool,
+    pub time: f32,
 }
 
-fn draw_stars(framebuffer: &mut Framebuffer, seed: u64) {
+fn draw_stars(framebuffer: &mut Framebuffer, num_stars: u32, seed: u64) {
     use rand::SeedableRng;
     let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
-    let num_stars = 600;
-
     for _ in 0..num_stars {
         let x = rng.gen_range(0..framebuffer.width);
         let y = rng.gen_range(0..framebuffer.height);
@@ -69,244 +104,381 @@ fn render(
     framebuffer: &mut Framebuffer,
     uniforms: &Uniforms,
     vertex_array: &[Vertex],
-    light: &Light,
-    shader_type: ShaderType,
-    time: f32,
+    lights: &[Light],
+    material: &dyn Material,
+    profiler: &mut Profiler,
 ) {
-    let mut transformed_vertices = Vec::with_capacity(vertex_array.len());
-    for vertex in vertex_array {
-        let transformed = vertex_shader(vertex, uniforms);
-        transformed_vertices.push(transformed);
+    let transformed_vertices: Vec<Vertex> = profiler.time(Stage::Vertex, || {
+        vertex_array.iter().map(|vertex| vertex_shader(vertex, uniforms)).collect()
+    });
+
+    let fragments = profiler.time(Stage::Raster, || {
+        let mut fragments = Vec::new();
+        for tri in transformed_vertices.chunks_exact(3) {
+            fragments.extend(triangle(&tri[0], &tri[1], &tri[2]));
+        }
+        fragments
+    });
+
+    profiler.time(Stage::Fragment, || {
+        for mut fragment in fragments {
+            if uniforms.sphere_uv_mode == SphereUvMode::PerFragment {
+                fragment.compute_sphere_uv();
+            }
+
+            let x = fragment.position.x as i32;
+            let y = fragment.position.y as i32;
+            if let Some(varying) = uniforms.debug_varying {
+                framebuffer.point(x, y, fragment.depth, fragment.debug_color(varying));
+                continue;
+            }
+
+            let alpha = material.opacity(&fragment, uniforms);
+            if alpha <= 0.0 {
+                continue;
+            }
+
+            let final_color = shade(material, &fragment, uniforms, lights);
+
+            if alpha < 1.0 {
+                framebuffer.blend_point(x, y, fragment.depth, final_color, alpha);
+            } else {
+                framebuffer.point(x, y, fragment.depth, final_color);
+            }
+        }
+    });
+}
+
+fn projection_matrix(framebuffer: &Framebuffer) -> Mat4 {
+    create_projection_matrix(PI / 3.0, framebuffer.width as f32 / framebuffer.height as f32, 0.1, 100.0)
+}
+
+fn viewport_matrix(framebuffer: &Framebuffer) -> Mat4 {
+    create_viewport_matrix(0.0, 0.0, framebuffer.width as f32, framebuffer.height as f32)
+}
+
+// Cuerpo bajo el cursor: el rayo va del plano cercano (profundidad -255 tras el viewport) al lejano
+fn pick_body(framebuffer: &Framebuffer, scene: &Scene, mouse: Vector2) -> Option<usize> {
+    let view = scene.camera.get_view_matrix();
+    let (projection, viewport) = (projection_matrix(framebuffer), viewport_matrix(framebuffer));
+    let near = unproject(Vec3A::new(mouse.x, mouse.y, -255.0), &view, &projection, &viewport)?;
+    let far = unproject(Vec3A::new(mouse.x, mouse.y, 255.0), &view, &projection, &viewport)?;
+    scene.pick(near, far - near)
+}
+
+fn draw_scene(framebuffer: &mut Framebuffer, scene: &mut Scene, options: &Options, time: f32, profiler: &mut Profiler) {
+    profiler.time(Stage::Update, || scene.update(time));
+
+    framebuffer.clear();
+    framebuffer.set_current_color(Color::new(200, 200, 255, 255));
+    draw_stars(framebuffer, scene.stars, scene.star_seed);
+
+    let view_matrix = scene.camera.get_view_matrix();
+    let projection_matrix = projection_matrix(framebuffer);
+    let viewport_matrix = viewport_matrix(framebuffer);
+
+    for item in scene.graph.draw_list() {
+        let draw = item.drawable;
+        let uniforms = Uniforms {
+            model_matrix: item.model_matrix,
+            normal_matrix: normal_matrix(&item.model_matrix),
+            view_matrix,
+            projection_matrix,
+            viewport_matrix,
+            camera_position: scene.camera.eye,
+            sphere_uv_mode: options.sphere_uv_mode,
+            debug_varying: options.debug_varying,
+            is_ring: draw.is_ring,
+            time,
+        };
+
+        let vertices = &scene.meshes[&draw.mesh].parts[draw.part].vertices;
+        render(framebuffer, &uniforms, vertices, &scene.lights, scene.material(&draw.material), profiler);
+    }
+
+    if scene.show_paths {
+        let uniforms = Uniforms {
+            model_matrix: Mat4::IDENTITY,
+            normal_matrix: Mat4::IDENTITY,
+            view_matrix,
+            projection_matrix,
+            viewport_matrix,
+            camera_position: scene.camera.eye,
+            sphere_uv_mode: options.sphere_uv_mode,
+            debug_varying: None,
+            is_ring: false,
+            time,
+        };
+        profiler.time(Stage::Paths, || draw_paths(framebuffer, scene, &uniforms));
     }
 
-    let mut triangles = Vec::new();
-    for i in (0..transformed_vertices.len()).step_by(3) {
-        if i + 2 < transformed_vertices.len() {
-            triangles.push([
-                transformed_vertices[i].clone(),
-                transformed_vertices[i + 1].clone(),
-                transformed_vertices[i + 2].clone(),
-            ]);
+    profiler.end_frame();
+}
+
+// Órbitas como elipses cerradas y estelas que se desvanecen con la edad
+fn draw_paths(framebuffer: &mut Framebuffer, scene: &Scene, uniforms: &Uniforms) {
+    for body in scene.bodies.iter().filter(|body| body.path.visible) {
+        let style = body.path;
+        if style.orbit
+            && let Some(points) = scene.orbit_path(body, ORBIT_SEGMENTS, uniforms.time)
+        {
+            polyline3d(framebuffer, uniforms, &points, true, style.color, style.opacity);
+        }
+
+        let trail = &body.trail.points;
+        for (older, newer) in trail.iter().zip(trail.iter().skip(1)) {
+            let age = (uniforms.time - newer.0) / style.trail;
+            let alpha = style.opacity * (1.0 - age).clamp(0.0, 1.0);
+            line3d(framebuffer, uniforms, older.1, newer.1, style.color, alpha);
         }
     }
+}
 
-    let mut fragments = Vec::new();
-    for tri in &triangles {
-        fragments.extend(triangle(&tri[0], &tri[1], &tri[2], light));
+fn run_headless(options: &Options, framebuffer: &mut Framebuffer, scene: &mut Scene) {
+    let path = options.record_path.as_deref().unwrap_or(DEFAULT_RECORDING_PATH);
+    let mut recorder = Recorder::new(path, options.record_fps, options.record_downscale, options.dither)
+        .expect("No se pudo iniciar la grabación");
+
+    let seconds = options.record_seconds.unwrap_or(5.0);
+    let frame_count = (seconds * options.record_fps as f32).round() as usize;
+    let time_step = TIME_STEP * FRAME_RATE / options.record_fps as f32;
+    if let Err(err) = recorder.check_capacity(framebuffer, frame_count) {
+        eprintln!("No se pueden grabar {frame_count} cuadros: {err}");
+        process::exit(1);
     }
 
-    for fragment in fragments {
-        let final_color = fragment_shaders(&fragment, uniforms, shader_type, time);
+    let mut profiler = Profiler::new();
+    let mut time: f32 = 0.0;
+    for _ in 0..frame_count {
+        time += time_step;
+        draw_scene(framebuffer, scene, options, time, &mut profiler);
+        recorder.capture(framebuffer).expect("la capacidad se comprobó antes de grabar");
+    }
 
-        framebuffer.point(
-            fragment.position.x as i32,
-            fragment.position.y as i32,
-            fragment.depth,
-            final_color,
+    if let Some(simulation) = &scene.simulation {
+        println!(
+            "Simulación ({}): deriva de energía {:+.2e}, deriva del momento {:.2e}",
+            simulation.integrator.name(),
+            simulation.energy_drift(),
+            simulation.momentum_drift()
         );
     }
+
+    if let Some(date) = scene.date(time) {
+        println!("Fecha final: {}", format_date(date));
+    }
+    if options.profile {
+        println!("{}", profiler.report());
+    }
+
+    finish_recording(recorder);
+}
+
+fn status_overlay(scene: &Scene, time: f32) -> Vec<String> {
+    if let Some(date) = scene.date(time) {
+        return vec![format!("Fecha {} - [D] salir  [J] ir a fecha  [N] simular", format_date(date))];
+    }
+    match &scene.simulation {
+        Some(simulation) => vec![
+            format!("Simulación N-cuerpos ({}) - [N] órbitas  [I] integrador", simulation.integrator.name()),
+            format!("Energía {:.4}  deriva {:+.2e}", simulation.energy(), simulation.energy_drift()),
+            format!("Deriva del momento {:.2e}", simulation.momentum_drift()),
+        ],
+        None => vec![format!(
+            "Órbitas keplerianas - [N] simular ({})  [D] fecha actual  [J] ir a fecha",
+            scene.integrator.name()
+        )],
+    }
+}
+
+enum DateEntry {
+    Editing,
+    Submit,
+    Cancel,
+}
+
+// Lee lo que se escribe en la entrada de fecha. J no aparece en una fecha, así que la cierra.
+fn edit_date(window: &mut RaylibHandle, text: &mut String) -> DateEntry {
+    while let Some(key) = window.get_char_pressed() {
+        match key {
+            'j' | 'J' => return DateEntry::Cancel,
+            '0'..='9' | '-' | ':' | ' ' | 'T' if text.len() < 16 => text.push(key),
+            _ => {}
+        }
+    }
+    if window.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+        text.pop();
+    }
+    if window.is_key_pressed(KeyboardKey::KEY_ENTER) {
+        return DateEntry::Submit;
+    }
+    DateEntry::Editing
+}
+
+fn finish_recording(recorder: Recorder) {
+    let path = recorder.path().display().to_string();
+    match recorder.finish() {
+        Ok(frames) => println!("Grabación guardada en {path} ({frames} cuadros)"),
+        Err(err) => eprintln!("No se pudo guardar la grabación {path}: {err}"),
+    }
 }
 
 fn main() {
+    let options = Options::from_args();
+
     let window_width = 1300;
     let window_height = 900;
 
-    let (mut window, raylib_thread) = raylib::init()
-        .size(window_width, window_height)
-        .title("Sistema Solar - Proyecto 3")
-        .log_level(TraceLogLevel::LOG_WARNING)
-        .build();
+    let registry = MaterialRegistry::with_builtin();
+    let mut scene = Scene::load(&options.scene_path, &registry, &options.planets).unwrap_or_else(|err| {
+        eprintln!("No se pudo cargar la escena: {err}");
+        process::exit(1);
+    });
+
+    scene.integrator = options.integrator;
+    scene.show_paths = options.paths;
+    if options.nbody {
+        scene.set_simulated(true, 0.0);
+    }
+    if options.date.is_some() {
+        scene.set_date(options.date, 0.0);
+    }
 
     let mut framebuffer = Framebuffer::new(window_width, window_height);
+    framebuffer.set_background_color(scene.background);
 
-    let mut camera = Camera::new(
-        Vector3::new(0.0, 8.0, 28.0),
-        Vector3::new(0.0, 0.0, 0.0),
-        Vector3::new(0.0, 1.0, 0.0),
-    );
+    if options.headless {
+        run_headless(&options, &mut framebuffer, &mut scene);
+        return;
+    }
 
-    let light = Light::new(Vector3::new(0.0, 0.0, 0.0));
+    run_window(&options, framebuffer, &mut scene);
+}
 
-    let obj = Obj::load("assets/models/sphere.obj").expect("No se pudo cargar sphere.obj");
-    let vertex_array = obj.get_vertex_array();
+fn run_window(options: &Options, mut framebuffer: Framebuffer, scene: &mut Scene) {
+    let (mut window, raylib_thread) = raylib::init()
+        .size(framebuffer.width, framebuffer.height)
+        .title("Sistema Solar - Proyecto 3")
+        .log_level(TraceLogLevel::LOG_WARNING)
+        .build();
+
+    let record_path = options.record_path.as_deref().unwrap_or(DEFAULT_RECORDING_PATH);
+    let capture_interval = ((FRAME_RATE / options.record_fps as f32).round() as u32).max(1);
+    let capture_fps = (FRAME_RATE / capture_interval as f32).round() as u32;
 
-    let spaceship_obj = Obj::load("assets/models/spaceship.obj").expect("No se pudo cargar spaceship.obj");
-    let spaceship_vertices = spaceship_obj.get_vertex_array();
+    let new_recorder = || {
+        Recorder::new(record_path, capture_fps, options.record_downscale, options.dither)
+            .map_err(|err| eprintln!("No se pudo iniciar la grabación: {err}"))
+            .ok()
+    };
 
-    framebuffer.set_background_color(Color::new(5, 5, 15, 255));
+    // Con --record se graba desde el inicio; R inicia/detiene la grabación
+    let mut recorder = if options.record_path.is_some() { new_recorder() } else { None };
+    let mut recording_start: f32 = 0.0;
+    let mut frame: u32 = 0;
 
     let mut time: f32 = 0.0;
+    let mut profiler = Profiler::new();
+    let mut show_profile = options.profile;
+    // Texto de la fecha a la que saltar mientras se escribe, y el último error al leerla
+    let mut date_entry: Option<String> = None;
+    let mut date_error: Option<String> = None;
 
     while !window.window_should_close() {
-        camera.process_input(&window);
-
-        framebuffer.clear();
-        framebuffer.set_current_color(Color::new(200, 200, 255, 255));
-        draw_stars(&mut framebuffer, 42);
-
-        let view_matrix = camera.get_view_matrix();
-        let projection_matrix = create_projection_matrix(
-            PI / 3.0,
-            window_width as f32 / window_height as f32,
-            0.1,
-            100.0,
-        );
-        let viewport_matrix =
-            create_viewport_matrix(0.0, 0.0, window_width as f32, window_height as f32);
-
-        time += 0.02;
-
-        let sun_model_matrix = create_model_matrix_y(
-            Vector3::new(0.0, 0.0, 0.0),
-            1.5,
-            time * 0.3,
-        );
-        let sun_uniforms = Uniforms {
-            model_matrix: sun_model_matrix,
-            view_matrix: view_matrix,
-            projection_matrix: projection_matrix,
-            viewport_matrix: viewport_matrix,
-            is_ring: false,
-        };
-        render(&mut framebuffer, &sun_uniforms, &vertex_array, &light, ShaderType::Sun, time);
-
-        let mars_translation = Vector3::new(
-            4.5 * (time * 1.5).cos(),
-            0.0,
-            4.5 * (time * 1.5).sin(),
-        );
-        let mars_model_matrix = create_model_matrix_y(
-            mars_translation,
-            0.8,
-            time * 3.5,
-        );
-        let mars_uniforms = Uniforms {
-            model_matrix: mars_model_matrix,
-            view_matrix: view_matrix,
-            projection_matrix: projection_matrix,
-            viewport_matrix: viewport_matrix,
-            is_ring: false,
-        };
-        render(&mut framebuffer, &mars_uniforms, &vertex_array, &light, ShaderType::Mars, time);
-
-        let mocca_translation = Vector3::new(
-            9.0 * (time * 1.2).cos(),
-            0.0,
-            9.0 * (time * 1.2).sin(),
-        );
-        let mocca_model_matrix = create_model_matrix_y(
-            mocca_translation,
-            0.75,
-            time * 3.6,
-        );
-        let mocca_uniforms = Uniforms {
-            model_matrix: mocca_model_matrix,
-            view_matrix: view_matrix,
-            projection_matrix: projection_matrix,
-            viewport_matrix: viewport_matrix,
-            is_ring: false,
-        };
-        render(&mut framebuffer, &mocca_uniforms, &vertex_array, &light, ShaderType::Mocca, time);
-
-        let saturn_translation = Vector3::new(
-            10.0 * (time * 3.8).cos(),
-            0.0,
-            10.0 * (time * 3.8).sin(),
-        );
-        let saturn_model_matrix = create_model_matrix_y(
-            saturn_translation,
-            1.1,
-            time * 6.0,
-        );
-        let saturn_uniforms = Uniforms {
-            model_matrix: saturn_model_matrix,
-            view_matrix: view_matrix,
-            projection_matrix: projection_matrix,
-            viewport_matrix: viewport_matrix,
-            is_ring: false,
-        };
-        render(&mut framebuffer, &saturn_uniforms, &vertex_array, &light, ShaderType::Saturn, time);
-
-        let saturn_ring_uniforms = Uniforms {
-            model_matrix: saturn_model_matrix,
-            view_matrix: view_matrix,
-            projection_matrix: projection_matrix,
-            viewport_matrix: viewport_matrix,
-            is_ring: true,
-        };
-        render(&mut framebuffer, &saturn_ring_uniforms, &vertex_array, &light, ShaderType::SaturnRing, time);
+        // Mientras se escribe una fecha las demás teclas (también las de la cámara) no
+        // disparan acciones
+        if let Some(text) = date_entry.as_mut() {
+            match edit_date(&mut window, text) {
+                DateEntry::Editing => {}
+                DateEntry::Cancel => {
+                    date_entry = None;
+                    date_error = None;
+                }
+                DateEntry::Submit => match ephemeris::parse_date(text) {
+                    Ok(date) => {
+                        scene.set_date(Some(date), time);
+                        date_entry = None;
+                        date_error = None;
+                    }
+                    Err(err) => date_error = Some(err),
+                },
+            }
+        } else {
+            scene.camera.process_input(&window);
+            if window.is_key_pressed(KeyboardKey::KEY_N) {
+                scene.set_simulated(scene.simulation.is_none(), time);
+            }
+            if window.is_key_pressed(KeyboardKey::KEY_I) {
+                scene.cycle_integrator();
+            }
+            if window.is_key_pressed(KeyboardKey::KEY_O) {
+                scene.show_paths = !scene.show_paths;
+            }
+            if window.is_key_pressed(KeyboardKey::KEY_P) {
+                show_profile = !show_profile;
+            }
+            if window.is_key_pressed(KeyboardKey::KEY_D) {
+                let date = if scene.calendar.is_some() { None } else { Some(ephemeris::now()) };
+                scene.set_date(date, time);
+            }
+            if window.is_key_pressed(KeyboardKey::KEY_J) {
+                date_entry = Some(String::new());
+            }
+            // Clic sobre un cuerpo para seguirlo; en el vacío, para dejar de seguir
+            if window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+                let body = pick_body(&framebuffer, scene, window.get_mouse_position());
+                scene.follow_body(body);
+            }
+            if window.is_key_pressed(KeyboardKey::KEY_R) {
+                match recorder.take() {
+                    Some(active) => finish_recording(active),
+                    None => {
+                        recorder = new_recorder();
+                        recording_start = time;
+                        frame = 0;
+                    }
+                }
+            }
+        }
 
-                // ====== Urano ======
-        let uranus_translation = Vector3::new(
-            17.5 * (time * 2.1).cos(),  // Órbita más grande y lenta que Saturno
-            0.0,
-            17.5 * (time * 2.1).sin(),
-        );
-        let uranus_model_matrix = create_model_matrix_y(
-            uranus_translation,
-            0.85,              // tamaño ligeramente menor que Saturno
-            time * 4.2,        // rotación más lenta
-        );
-        let uranus_uniforms = Uniforms {
-            model_matrix: uranus_model_matrix,
-            view_matrix,
-            projection_matrix,
-            viewport_matrix,
-            is_ring: false,
-        };
-        render(&mut framebuffer, &uranus_uniforms, &vertex_array, &light, ShaderType::Uranus, time);
+        time += TIME_STEP;
 
-        // ====== Neptuno ======
-        let neptune_translation = Vector3::new(
-            26.0 * (time * 1.7).cos(),  // Órbita aún más grande y más lenta
-            0.0,
-            26.0 * (time * 1.7).sin(),
-        );
-        let neptune_model_matrix = create_model_matrix_y(
-            neptune_translation,
-            0.82,
-            time * 2.8,
-        );
-        let neptune_uniforms = Uniforms {
-            model_matrix: neptune_model_matrix,
-            view_matrix,
-            projection_matrix,
-            viewport_matrix,
-            is_ring: false,
-        };
-        render(&mut framebuffer, &neptune_uniforms, &vertex_array, &light, ShaderType::Neptune, time);
-
-        // ====== Nave Espacial ======
-        let orbit_radius = 100.0;
-        let orbit_speed = 0.8;   // rad/s → controla traslación
-        let spin_speed  = -1.0;   // rad/s → controla rotación (igual = 1:1)
-        let elevation   = 25.5;
-        let scale       = 0.08;
-
-        let angle = time * orbit_speed;
-        let spaceship_translation = Vector3::new(
-            orbit_radius * angle.cos(),
-            elevation,
-            orbit_radius * angle.sin(),
-        );
+        draw_scene(&mut framebuffer, scene, options, time, &mut profiler);
 
-        let spaceship_model_matrix = create_model_matrix_y(
-            spaceship_translation,
-            scale,
-            time * spin_speed,   // ✅ independiente
-        );
+        if let Some(active) = recorder.as_mut() {
+            // Al llegar al tope de memoria se guarda lo grabado hasta ahí
+            let mut full = false;
+            if frame.is_multiple_of(capture_interval)
+                && let Err(err) = active.capture(&framebuffer)
+            {
+                eprintln!("Grabación detenida: {err}");
+                full = true;
+            }
+            frame += 1;
 
-        let spaceship_uniforms = Uniforms {
-            model_matrix: spaceship_model_matrix,
-            view_matrix,
-            projection_matrix,
-            viewport_matrix,
-            is_ring: false,
-        };
+            let elapsed = (time - recording_start) / (TIME_STEP * FRAME_RATE);
+            if (full || options.record_seconds.is_some_and(|seconds| elapsed >= seconds))
+                && let Some(active) = recorder.take()
+            {
+                finish_recording(active);
+            }
+        }
 
-        render(&mut framebuffer, &spaceship_uniforms, &spaceship_vertices, &light, ShaderType::Spaceship, time);
-        
-        framebuffer.swap_buffers(&mut window, &raylib_thread);
+        let mut overlay = status_overlay(scene, time);
+        if let Some(text) = &date_entry {
+            overlay.push(format!("Ir a fecha (AAAA-MM-DD [HH:MM] UTC, Enter confirma, J cancela): {text}_"));
+        }
+        if let Some(err) = &date_error {
+            overlay.push(err.clone());
+        }
+        if show_profile {
+            overlay.extend(profiler.overlay());
+        }
+        framebuffer.swap_buffers(&mut window, &raylib_thread, &overlay);
         thread::sleep(Duration::from_millis(16));
     }
-}
\ No newline at end of file
+
+    if let Some(active) = recorder.take() {
+        finish_recording(active);
+    }
+}
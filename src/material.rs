@@ -0,0 +1,277 @@
+use crate::Uniforms;
+use crate::fragment::Fragment;
+use crate::light::Light;
+use crate::lighting::{illuminate, LightingModel, SpecularModel, Surface};
+use crate::obj_loader::ObjMaterial;
+use crate::texture::{FilterMode, Texture, WrapMode};
+use crate::varyings::Varying;
+use glam::Vec3A;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParamValue {
+    Float(f32),
+    Color(Vec3A),
+}
+
+// Parámetros con nombre que cada material expone (colores, escalas, velocidades)
+#[derive(Debug, Clone, Default)]
+pub struct MaterialParams {
+    values: HashMap<String, ParamValue>,
+}
+
+impl MaterialParams {
+    pub fn new() -> Self {
+        MaterialParams::default()
+    }
+
+    pub fn with_float(mut self, name: &str, value: f32) -> Self {
+        self.set(name, ParamValue::Float(value));
+        self
+    }
+
+    pub fn with_color(mut self, name: &str, value: Vec3A) -> Self {
+        self.set(name, ParamValue::Color(value));
+        self
+    }
+
+    pub fn set(&mut self, name: &str, value: ParamValue) {
+        self.values.insert(name.to_string(), value);
+    }
+
+    pub fn get(&self, name: &str) -> Option<ParamValue> {
+        self.values.get(name).copied()
+    }
+
+    pub fn float(&self, name: &str, default: f32) -> f32 {
+        match self.values.get(name) {
+            Some(ParamValue::Float(value)) => *value,
+            _ => default,
+        }
+    }
+
+    pub fn color(&self, name: &str, default: Vec3A) -> Vec3A {
+        match self.values.get(name) {
+            Some(ParamValue::Color(value)) => *value,
+            _ => default,
+        }
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.values.keys().map(String::as_str)
+    }
+}
+
+pub trait Material {
+    fn name(&self) -> &str;
+
+    // Color de la superficie antes de aplicar la iluminación
+    fn surface_color(&self, fragment: &Fragment, uniforms: &Uniforms) -> Vec3A;
+
+    // Luz propia que se suma después de iluminar la superficie
+    fn emission(&self, _fragment: &Fragment, _uniforms: &Uniforms) -> Vec3A {
+        Vec3A::ZERO
+    }
+
+    // Normal con la que se ilumina; permite relieve procedural (bump mapping)
+    fn normal(&self, fragment: &Fragment, _uniforms: &Uniforms) -> Vec3A {
+        fragment.normal
+    }
+
+    fn lighting(&self) -> LightingModel {
+        LightingModel::default()
+    }
+
+    // Puede variar por fragmento (p. ej. solo los casquetes polares brillan)
+    fn specular(&self, _fragment: &Fragment, _uniforms: &Uniforms) -> SpecularModel {
+        SpecularModel::None
+    }
+
+    // 1 = opaco; valores menores se mezclan con lo ya dibujado
+    fn opacity(&self, _fragment: &Fragment, _uniforms: &Uniforms) -> f32 {
+        1.0
+    }
+
+    fn params(&self) -> &MaterialParams;
+
+    fn params_mut(&mut self) -> &mut MaterialParams;
+}
+
+pub type MaterialFactory = Box<dyn Fn() -> Box<dyn Material>>;
+
+pub struct MaterialRegistry {
+    factories: HashMap<String, MaterialFactory>,
+}
+
+impl MaterialRegistry {
+    pub fn new() -> Self {
+        MaterialRegistry {
+            factories: HashMap::new(),
+        }
+    }
+
+    pub fn with_builtin() -> Self {
+        let mut registry = MaterialRegistry::new();
+        crate::shaders::register_builtin(&mut registry);
+        registry
+    }
+
+    pub fn register<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn() -> Box<dyn Material> + 'static,
+    {
+        self.factories.insert(name.to_string(), Box::new(factory));
+    }
+
+    pub fn create(&self, name: &str) -> Option<Box<dyn Material>> {
+        self.factories.get(name).map(|factory| factory())
+    }
+}
+
+impl Default for MaterialRegistry {
+    fn default() -> Self {
+        MaterialRegistry::new()
+    }
+}
+
+// Adapta un material MTL (Kd, Ks, Ns, Ke, d, illum) al sistema de materiales.
+// Si tiene un material base, Kd tiñe su color procedural en lugar de reemplazarlo;
+// `map_Kd` se muestrea con la UV del modelo y multiplica a Kd.
+// Los valores del MTL van con prefijo `mtl_` en los parámetros del material base, de modo que
+// los de ambos (p. ej. `metallic` de la nave y `mtl_diffuse`) se ajustan desde la escena.
+pub struct MtlMaterial {
+    name: String,
+    illum: u8,
+    base: Option<Box<dyn Material>>,
+    diffuse_map: Option<Texture>,
+    // Solo se usan cuando no hay material base
+    params: MaterialParams,
+}
+
+impl MtlMaterial {
+    pub fn new(material: &ObjMaterial, base: Option<Box<dyn Material>>) -> Self {
+        let diffuse_map = material.diffuse_texture.as_ref().and_then(|map| {
+            Texture::load(&map.path)
+                .map(|texture| texture.with_wrap(map.wrap).with_mipmaps())
+                .map_err(|err| eprintln!("{err}"))
+                .ok()
+        });
+
+        let mut mtl = MtlMaterial {
+            name: material.name.clone(),
+            illum: material.illum,
+            base,
+            diffuse_map,
+            params: MaterialParams::new(),
+        };
+        let params = mtl.params_mut();
+        params.set("mtl_diffuse", ParamValue::Color(material.diffuse));
+        params.set("mtl_specular", ParamValue::Color(material.specular));
+        params.set("mtl_shininess", ParamValue::Float(material.shininess));
+        params.set("mtl_emissive", ParamValue::Color(material.emissive));
+        params.set("mtl_dissolve", ParamValue::Float(material.dissolve));
+        mtl
+    }
+
+    // Repetición y filtrado del `map_Kd`; `wrap` reemplaza a la opción `-clamp` del MTL
+    pub fn with_texture_options(mut self, wrap: Option<WrapMode>, filter: FilterMode) -> Self {
+        self.diffuse_map = self.diffuse_map.map(|texture| {
+            let texture = texture.with_filter(filter);
+            match wrap {
+                Some(wrap) => texture.with_wrap(wrap),
+                None => texture,
+            }
+        });
+        self
+    }
+}
+
+impl Material for MtlMaterial {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn surface_color(&self, fragment: &Fragment, uniforms: &Uniforms) -> Vec3A {
+        let mut diffuse = self.params().color("mtl_diffuse", Vec3A::ONE);
+        if let Some(texture) = &self.diffuse_map {
+            diffuse *= texture.sample_varying(fragment, Varying::Uv);
+        }
+        match &self.base {
+            Some(base) => base.surface_color(fragment, uniforms) * diffuse,
+            None => fragment.vertex_color() * diffuse,
+        }
+    }
+
+    fn emission(&self, fragment: &Fragment, uniforms: &Uniforms) -> Vec3A {
+        let base = self
+            .base
+            .as_ref()
+            .map_or(Vec3A::ZERO, |base| base.emission(fragment, uniforms));
+        base + self.params().color("mtl_emissive", Vec3A::ZERO)
+    }
+
+    fn normal(&self, fragment: &Fragment, uniforms: &Uniforms) -> Vec3A {
+        self.base.as_ref().map_or(fragment.normal, |base| base.normal(fragment, uniforms))
+    }
+
+    // illum 0: solo color; 1: difuso; 2 o más: difuso + especular
+    fn lighting(&self) -> LightingModel {
+        match self.illum {
+            0 => LightingModel::Unlit,
+            _ => self.base.as_ref().map_or(LightingModel::Lambert, |base| base.lighting()),
+        }
+    }
+
+    // Con material base manda su modelo especular (p. ej. el GGX de la nave); Ks/Ns solo se usan
+    // cuando el MTL va solo, y Ks conserva su color
+    fn specular(&self, fragment: &Fragment, uniforms: &Uniforms) -> SpecularModel {
+        if self.illum < 2 {
+            return SpecularModel::None;
+        }
+        if let Some(base) = &self.base {
+            return base.specular(fragment, uniforms);
+        }
+        let color = self.params().color("mtl_specular", Vec3A::ZERO);
+        if color.x.max(color.y).max(color.z) <= 0.0 {
+            return SpecularModel::None;
+        }
+
+        SpecularModel::BlinnPhong {
+            shininess: self.params().float("mtl_shininess", 32.0).max(1.0),
+            color,
+        }
+    }
+
+    fn opacity(&self, fragment: &Fragment, uniforms: &Uniforms) -> f32 {
+        let base = self.base.as_ref().map_or(1.0, |base| base.opacity(fragment, uniforms));
+        (base * self.params().float("mtl_dissolve", 1.0)).clamp(0.0, 1.0)
+    }
+
+    fn params(&self) -> &MaterialParams {
+        self.base.as_ref().map_or(&self.params, |base| base.params())
+    }
+
+    fn params_mut(&mut self) -> &mut MaterialParams {
+        match &mut self.base {
+            Some(base) => base.params_mut(),
+            None => &mut self.params,
+        }
+    }
+}
+
+pub fn shade(material: &dyn Material, fragment: &Fragment, uniforms: &Uniforms, lights: &[Light]) -> Vec3A {
+    let surface = Surface {
+        albedo: material.surface_color(fragment, uniforms),
+        position: fragment.world_position,
+        normal: material.normal(fragment, uniforms),
+    };
+    let lighting = illuminate(
+        material.lighting(),
+        material.specular(fragment, uniforms),
+        &surface,
+        lights,
+        uniforms.camera_position,
+    );
+
+    surface.albedo * lighting.diffuse + lighting.specular + material.emission(fragment, uniforms)
+}
@@ -1,124 +1,181 @@
-use raylib::prelude::*;
 
-fn dot_product(a: &Vector3, b: &Vector3) -> f32 {
-    a.x * b.x + a.y * b.y + a.z * b.z
-}
+use glam::{Mat4, Quat, Vec3A, Vec4};
 
+// Matriz escrita por filas, como se lee en papel; glam la guarda por columnas
+#[allow(clippy::too_many_arguments)]
 pub fn new_matrix4(
     r0c0: f32, r0c1: f32, r0c2: f32, r0c3: f32,
     r1c0: f32, r1c1: f32, r1c2: f32, r1c3: f32,
     r2c0: f32, r2c1: f32, r2c2: f32, r2c3: f32,
     r3c0: f32, r3c1: f32, r3c2: f32, r3c3: f32,
-) -> Matrix {
-    Matrix {
-        m0: r0c0, m1: r1c0, m2: r2c0, m3: r3c0,
-        m4: r0c1, m5: r1c1, m6: r2c1, m7: r3c1,
-        m8: r0c2, m9: r1c2, m10: r2c2, m11: r3c2,
-        m12: r0c3, m13: r1c3, m14: r2c3, m15: r3c3,
+) -> Mat4 {
+    Mat4::from_cols_array(&[
+        r0c0, r1c0, r2c0, r3c0,
+        r0c1, r1c1, r2c1, r3c1,
+        r0c2, r1c2, r2c2, r3c2,
+        r0c3, r1c3, r2c3, r3c3,
+    ])
+}
+
+// Inversa; None si la matriz es singular
+pub fn inverse(m: &Mat4) -> Option<Mat4> {
+    if m.determinant().abs() < 1e-12 {
+        return None;
+    }
+    Some(m.inverse())
+}
+
+// Inversa traspuesta de la parte 3x3, para llevar normales a mundo sin que la escala no
+// uniforme las tuerza. Es la matriz de cofactores dividida por el determinante, cuyas
+// columnas son c1×c2, c2×c0 y c0×c1.
+pub fn normal_matrix(model: &Mat4) -> Mat4 {
+    let c0 = Vec3A::from(model.x_axis);
+    let c1 = Vec3A::from(model.y_axis);
+    let c2 = Vec3A::from(model.z_axis);
+
+    let det = c0.dot(c1.cross(c2));
+    if det.abs() < 1e-12 {
+        return Mat4::IDENTITY;
+    }
+    let inv_det = 1.0 / det;
+    let n0 = c1.cross(c2) * inv_det;
+    let n1 = c2.cross(c0) * inv_det;
+    let n2 = c0.cross(c1) * inv_det;
+
+    Mat4::from_cols(n0.extend(0.0), n1.extend(0.0), n2.extend(0.0), Vec4::W)
+}
+
+// Punto (w = 1) con división perspectiva
+pub fn transform_point(m: &Mat4, p: Vec3A) -> Vec3A {
+    let p = *m * p.extend(1.0);
+    if p.w != 0.0 && p.w != 1.0 {
+        Vec3A::from(p / p.w)
+    } else {
+        Vec3A::from(p)
     }
 }
 
-pub fn create_model_matrix_y(
-    translation: Vector3,
-    scale: f32,
-    rotation_y: f32,
-) -> Matrix {
-    let (sin_y, cos_y) = rotation_y.sin_cos();
-
-    let rotation_matrix_y = new_matrix4(
-        cos_y,  0.0, sin_y, 0.0,
-        0.0,    1.0, 0.0,   0.0,
-        -sin_y, 0.0, cos_y, 0.0,
-        0.0,    0.0, 0.0,   1.0
-    );
-
-    let scale_matrix = new_matrix4(
-        scale, 0.0,   0.0,   0.0,
-        0.0,   scale, 0.0,   0.0,
-        0.0,   0.0,   scale, 0.0,
-        0.0,   0.0,   0.0,   1.0
-    );
-
-    let translation_matrix = new_matrix4(
-        1.0, 0.0, 0.0, translation.x,
-        0.0, 1.0, 0.0, translation.y,
-        0.0, 0.0, 1.0, translation.z,
-        0.0, 0.0, 0.0, 1.0
-    );
-
-    translation_matrix * scale_matrix * rotation_matrix_y
+// ====== Cuaterniones ======
+
+// El eje no necesita venir normalizado
+pub fn quaternion_from_axis_angle(axis: Vec3A, angle: f32) -> Quat {
+    Quat::from_axis_angle(axis.normalize().into(), angle)
+}
+
+// Rotación que lleva +Z local a `forward` y deja +Y lo más cerca posible de `up`
+pub fn quaternion_look_rotation(forward: Vec3A, up: Vec3A) -> Quat {
+    let z = forward.normalize();
+    let mut x = up.cross(z);
+    if x.length() < 1e-6 {
+        // `up` paralelo a `forward`: cualquier perpendicular sirve
+        x = Vec3A::X.cross(z);
+        if x.length() < 1e-6 {
+            x = Vec3A::Y.cross(z);
+        }
+    }
+    let x = x.normalize();
+    let y = z.cross(x);
+    quaternion_from_basis(x, y, z)
+}
+
+// Cuaternión de una matriz de rotación dada por sus columnas (método de Shepperd)
+pub fn quaternion_from_basis(x: Vec3A, y: Vec3A, z: Vec3A) -> Quat {
+    let trace = x.x + y.y + z.z;
+    let q = if trace > 0.0 {
+        let s = (trace + 1.0).sqrt() * 2.0;
+        Quat::from_xyzw((y.z - z.y) / s, (z.x - x.z) / s, (x.y - y.x) / s, 0.25 * s)
+    } else if x.x > y.y && x.x > z.z {
+        let s = (1.0 + x.x - y.y - z.z).sqrt() * 2.0;
+        Quat::from_xyzw(0.25 * s, (y.x + x.y) / s, (z.x + x.z) / s, (y.z - z.y) / s)
+    } else if y.y > z.z {
+        let s = (1.0 + y.y - x.x - z.z).sqrt() * 2.0;
+        Quat::from_xyzw((y.x + x.y) / s, 0.25 * s, (z.y + y.z) / s, (z.x - x.z) / s)
+    } else {
+        let s = (1.0 + z.z - x.x - y.y).sqrt() * 2.0;
+        Quat::from_xyzw((z.x + x.z) / s, (z.y + y.z) / s, 0.25 * s, (x.y - y.x) / s)
+    };
+    q.normalize()
+}
+
+// ====== Transformaciones TRS ======
+
+// Escala, luego rotación y luego traslación (T·R·S). Para componer se usan las matrices:
+// con escala no uniforme y rotación aparece cizalla, que un TRS no puede representar.
+#[derive(Debug, Clone, Copy)]
+pub struct Transform {
+    pub translation: Vec3A,
+    pub rotation: Quat,
+    pub scale: Vec3A,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform {
+            translation: Vec3A::ZERO,
+            rotation: Quat::IDENTITY,
+            scale: Vec3A::ONE,
+        }
+    }
 }
 
-#[allow(dead_code)]
-pub fn create_model_matrix(translation: Vector3, scale: f32, rotation: Vector3) -> Matrix {
-    let (sin_x, cos_x) = rotation.x.sin_cos();
-    let (sin_y, cos_y) = rotation.y.sin_cos();
-    let (sin_z, cos_z) = rotation.z.sin_cos();
-
-    let rx = new_matrix4(
-        1.0, 0.0,    0.0,    0.0,
-        0.0, cos_x,  -sin_x, 0.0,
-        0.0, sin_x,  cos_x,  0.0,
-        0.0, 0.0,    0.0,    1.0
-    );
-
-    let ry = new_matrix4(
-        cos_y,  0.0, sin_y, 0.0,
-        0.0,    1.0, 0.0,   0.0,
-        -sin_y, 0.0, cos_y, 0.0,
-        0.0,    0.0, 0.0,   1.0
-    );
-
-    let rz = new_matrix4(
-        cos_z, -sin_z, 0.0, 0.0,
-        sin_z, cos_z,  0.0, 0.0,
-        0.0,   0.0,    1.0, 0.0,
-        0.0,   0.0,    0.0, 1.0
-    );
-
-    let rotation_matrix = rz * ry * rx;
-    let scale_matrix = new_matrix4(scale, 0.0, 0.0, 0.0, 0.0, scale, 0.0, 0.0, 0.0, 0.0, scale, 0.0, 0.0, 0.0, 0.0, 1.0);
-    let translation_matrix = new_matrix4(1.0, 0.0, 0.0, translation.x, 0.0, 1.0, 0.0, translation.y, 0.0, 0.0, 1.0, translation.z, 0.0, 0.0, 0.0, 1.0);
-
-    translation_matrix * scale_matrix * rotation_matrix
+impl Transform {
+    pub fn from_translation(translation: Vec3A) -> Self {
+        Transform { translation, ..Transform::default() }
+    }
+
+    pub fn with_rotation(mut self, rotation: Quat) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_non_uniform_scale(mut self, scale: Vec3A) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    // Ubicado en `eye` y mirando hacia `target` (su +Z apunta al objetivo)
+    pub fn looking_at(eye: Vec3A, target: Vec3A, up: Vec3A) -> Self {
+        Transform::from_translation(eye).with_rotation(quaternion_look_rotation(target - eye, up))
+    }
+
+    // T·R·S: escala y gira el modelo en su origen y después lo traslada. La versión original
+    // (`create_model_matrix`) escribía `traslación * escala * rotación` con el `*` de raylib,
+    // que multiplica al revés y daba R·S·T: la posición orbital quedaba escalada por el tamaño
+    // del cuerpo y girada por su rotación propia (la nave a radio 100 con escala 0.08 se veía
+    // a radio 8).
+    pub fn matrix(&self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(self.scale.into(), self.rotation, self.translation.into())
+    }
+
+    pub fn transform_vector(&self, v: Vec3A) -> Vec3A {
+        self.rotation * (v * self.scale)
+    }
+
+    // Interpolación: lineal en traslación y escala, esférica en la rotación. q y -q son la
+    // misma rotación; el slerp de glam toma el arco corto entre los dos.
+    pub fn lerp(&self, other: &Transform, t: f32) -> Transform {
+        Transform {
+            translation: self.translation.lerp(other.translation, t),
+            rotation: self.rotation.slerp(other.rotation, t).normalize(),
+            scale: self.scale.lerp(other.scale, t),
+        }
+    }
 }
 
-pub fn create_view_matrix(eye: Vector3, target: Vector3, up: Vector3) -> Matrix {
-    let mut forward = Vector3::new(target.x - eye.x, target.y - eye.y, target.z - eye.z);
-    let len = (forward.x * forward.x + forward.y * forward.y + forward.z * forward.z).sqrt();
-    if len > 0.0 {
-        forward.x /= len;
-        forward.y /= len;
-        forward.z /= len;
-    }
-
-    let mut right = Vector3::new(
-        forward.y * up.z - forward.z * up.y,
-        forward.z * up.x - forward.x * up.z,
-        forward.x * up.y - forward.y * up.x,
-    );
-    let len = (right.x * right.x + right.y * right.y + right.z * right.z).sqrt();
-    if len > 0.0 {
-        right.x /= len;
-        right.y /= len;
-        right.z /= len;
-    }
-
-    let up = Vector3::new(
-        right.y * forward.z - right.z * forward.y,
-        right.z * forward.x - right.x * forward.z,
-        right.x * forward.y - right.y * forward.x,
-    );
+pub fn create_view_matrix(eye: Vec3A, target: Vec3A, up: Vec3A) -> Mat4 {
+    let forward = (target - eye).normalize_or_zero();
+    let right = forward.cross(up).normalize_or_zero();
+    let up = right.cross(forward);
 
     new_matrix4(
-        right.x, right.y, right.z, -dot_product(&right, &eye),
-        up.x, up.y, up.z, -dot_product(&up, &eye),
-        -forward.x, -forward.y, -forward.z, dot_product(&forward, &eye),
+        right.x, right.y, right.z, -right.dot(eye),
+        up.x, up.y, up.z, -up.dot(eye),
+        -forward.x, -forward.y, -forward.z, forward.dot(eye),
         0.0, 0.0, 0.0, 1.0,
     )
 }
 
-pub fn create_projection_matrix(fov_y: f32, aspect: f32, near: f32, far: f32) -> Matrix {
+pub fn create_projection_matrix(fov_y: f32, aspect: f32, near: f32, far: f32) -> Mat4 {
     let tan_half_fov = (fov_y / 2.0).tan();
     new_matrix4(
         1.0 / (aspect * tan_half_fov), 0.0, 0.0, 0.0,
@@ -128,7 +185,7 @@ pub fn create_projection_matrix(fov_y: f32, aspect: f32, near: f32, far: f32) ->
     )
 }
 
-pub fn create_viewport_matrix(x: f32, y: f32, width: f32, height: f32) -> Matrix {
+pub fn create_viewport_matrix(x: f32, y: f32, width: f32, height: f32) -> Mat4 {
     let hw = width * 0.5;
     let hh = height * 0.5;
     new_matrix4(
@@ -137,4 +194,141 @@ pub fn create_viewport_matrix(x: f32, y: f32, width: f32, height: f32) -> Matrix
         0.0, 0.0, 255.0, 0.0,
         0.0, 0.0, 0.0, 1.0,
     )
-}
\ No newline at end of file
+}
+
+// De pantalla (x, y en píxeles, z como profundidad del framebuffer) a mundo. La vista de
+// viewport es afín, así que basta invertir el producto completo y dividir por w.
+pub fn unproject(screen: Vec3A, view: &Mat4, projection: &Mat4, viewport: &Mat4) -> Option<Vec3A> {
+    let combined = *viewport * *projection * *view;
+    inverse(&combined).map(|inv| transform_point(&inv, screen))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_matrix_near(a: &Mat4, b: &Mat4) {
+        assert!(a.abs_diff_eq(*b, 1e-4), "{a:?} != {b:?}");
+    }
+
+    fn assert_vector_near(a: Vec3A, b: Vec3A) {
+        assert!((a - b).length() < 1e-3, "{a:?} != {b:?}");
+    }
+
+    // Dirección (w = 0) por la parte 3x3 de la matriz
+    fn apply(m: &Mat4, v: Vec3A) -> Vec3A {
+        m.transform_vector3a(v)
+    }
+
+    fn rotation(axis: Vec3A, degrees: f32) -> Quat {
+        quaternion_from_axis_angle(axis, degrees.to_radians())
+    }
+
+    fn sample_transform() -> Transform {
+        Transform::from_translation(Vec3A::new(1.0, 2.0, 3.0))
+            .with_rotation(rotation(Vec3A::new(0.3, -1.0, 0.5), 70.0))
+            .with_non_uniform_scale(Vec3A::new(3.0, 0.5, 1.0))
+    }
+
+    #[test]
+    fn inverse_times_matrix_is_identity() {
+        let model = sample_transform().matrix();
+        let projection = create_projection_matrix(1.0, 16.0 / 9.0, 0.1, 100.0);
+        for m in [model, projection, projection * model] {
+            let inv = inverse(&m).expect("la matriz es invertible");
+            assert_matrix_near(&(inv * m), &Mat4::IDENTITY);
+            assert_matrix_near(&(m * inv), &Mat4::IDENTITY);
+        }
+    }
+
+    #[test]
+    fn inverse_of_singular_is_none() {
+        let flat = Transform::default().with_non_uniform_scale(Vec3A::new(1.0, 0.0, 1.0)).matrix();
+        assert!(inverse(&flat).is_none());
+    }
+
+    // T·R·S: el origen va a la traslación y cada eje se escala antes de girar
+    #[test]
+    fn matrix_applies_scale_then_rotation_then_translation() {
+        let pose = sample_transform();
+        let m = pose.matrix();
+        assert_vector_near(transform_point(&m, Vec3A::ZERO), pose.translation);
+        let x = Vec3A::new(1.0, 0.0, 0.0);
+        assert_vector_near(transform_point(&m, x), pose.translation + pose.rotation * (x * 3.0));
+    }
+
+    #[test]
+    fn normal_matrix_keeps_normals_perpendicular_under_non_uniform_scale() {
+        let model = sample_transform().matrix();
+        let normals = normal_matrix(&model);
+
+        // Plano inclinado: la normal debe seguir perpendicular a sus tangentes ya transformadas
+        let tangent_a = Vec3A::new(1.0, 1.0, 0.0);
+        let tangent_b = Vec3A::new(0.0, 1.0, 1.0);
+        let normal = tangent_a.cross(tangent_b);
+
+        let world_normal = apply(&normals, normal);
+        let naive_normal = apply(&model, normal);
+        for tangent in [tangent_a, tangent_b] {
+            let world_tangent = apply(&model, tangent);
+            assert!(world_normal.normalize().dot(world_tangent.normalize()).abs() < 1e-4);
+            assert!(naive_normal.normalize().dot(world_tangent.normalize()).abs() > 1e-2);
+        }
+    }
+
+    // Los ángulos cerca de 180° recorren las ramas de Shepperd con traza negativa
+    #[test]
+    fn quaternion_from_basis_round_trip() {
+        let rotations = [
+            rotation(Vec3A::new(0.0, 1.0, 0.0), 0.0),
+            rotation(Vec3A::new(0.3, 1.2, -0.7), 75.0),
+            rotation(Vec3A::new(1.0, 0.1, 0.2), 175.0),
+            rotation(Vec3A::new(0.1, 1.0, 0.0), 178.0),
+            rotation(Vec3A::new(-0.2, 0.1, 1.0), 170.0),
+        ];
+        for q in rotations {
+            let back = quaternion_from_basis(q * Vec3A::X, q * Vec3A::Y, q * Vec3A::Z);
+
+            // q y -q son la misma rotación
+            assert!((q.dot(back).abs() - 1.0).abs() < 1e-4, "{q:?} != {back:?}");
+            assert_matrix_near(&Mat4::from_quat(back), &Mat4::from_quat(q));
+        }
+    }
+
+    #[test]
+    fn looking_at_points_z_to_the_target() {
+        let eye = Vec3A::new(2.0, 1.0, -3.0);
+        for (target, up) in [
+            (Vec3A::new(-1.0, 4.0, 5.0), Vec3A::new(0.0, 1.0, 0.0)),
+            // `up` paralelo a la mirada
+            (Vec3A::new(2.0, 6.0, -3.0), Vec3A::new(0.0, 1.0, 0.0)),
+        ] {
+            let pose = Transform::looking_at(eye, target, up);
+            assert_vector_near(pose.translation, eye);
+            assert_vector_near(pose.transform_vector(Vec3A::new(0.0, 0.0, 1.0)), (target - eye).normalize());
+        }
+    }
+
+    #[test]
+    fn lerp_takes_the_shortest_arc() {
+        let axis = Vec3A::new(0.0, 1.0, 0.0);
+        let from = Transform::default().with_rotation(rotation(axis, 0.0));
+        // 350° es lo mismo que -10°, pero su cuaternión cae en el hemisferio opuesto
+        let to = Transform::default().with_rotation(rotation(axis, 350.0));
+        let half = from.lerp(&to, 0.5);
+        assert_matrix_near(&Mat4::from_quat(half.rotation), &Mat4::from_quat(rotation(axis, -5.0)));
+    }
+
+    #[test]
+    fn unproject_undoes_projection() {
+        let view = create_view_matrix(Vec3A::new(0.0, 3.0, 8.0), Vec3A::ZERO, Vec3A::new(0.0, 1.0, 0.0));
+        let projection = create_projection_matrix(1.0, 4.0 / 3.0, 0.1, 100.0);
+        let viewport = create_viewport_matrix(0.0, 0.0, 800.0, 600.0);
+        let combined = viewport * projection * view;
+
+        let point = Vec3A::new(1.5, -0.5, 2.0);
+        let screen = transform_point(&combined, point);
+        assert!((0.0..800.0).contains(&screen.x) && (0.0..600.0).contains(&screen.y));
+        assert_vector_near(unproject(screen, &view, &projection, &viewport).unwrap(), point);
+    }
+}
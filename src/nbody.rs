@@ -0,0 +1,366 @@
+use glam::Vec3A;
+
+// Paso máximo de los integradores de paso fijo; cada cuadro se parte en subpasos
+const MAX_STEP: f32 = 0.005;
+// Suavizado de Plummer: evita aceleraciones infinitas en los encuentros cercanos
+const SOFTENING: f32 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Integrator {
+    // Simpléctico (kick-drift-kick): la energía oscila pero no deriva
+    #[default]
+    Leapfrog,
+    Rk4,
+    // Dormand–Prince 5(4) con paso adaptativo
+    Rk45,
+}
+
+impl Integrator {
+    pub const ALL: [Integrator; 3] = [Integrator::Leapfrog, Integrator::Rk4, Integrator::Rk45];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Integrator::Leapfrog => "leapfrog",
+            Integrator::Rk4 => "rk4",
+            Integrator::Rk45 => "rk45",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|integrator| integrator.name() == name)
+    }
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&integrator| integrator == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Particle {
+    pub mass: f32,
+    pub position: Vec3A,
+    pub velocity: Vec3A,
+}
+
+// Estado de una partícula (o su derivada) para los Runge-Kutta
+#[derive(Debug, Clone, Copy)]
+struct State {
+    position: Vec3A,
+    velocity: Vec3A,
+}
+
+// Simulación gravitatoria con G = 1 (las mismas unidades que `mass` en la escena)
+pub struct NBody {
+    pub particles: Vec<Particle>,
+    pub integrator: Integrator,
+    pub tolerance: f32,
+    pub time: f32,
+    // Último paso aceptado por RK45, punto de partida del siguiente
+    adaptive_step: f32,
+    // Aceleraciones del último kick de leapfrog: sirven para el primer kick del paso siguiente.
+    // Vacío cuando el estado cambió por otro camino (al crear la simulación o con otro integrador).
+    leapfrog_accelerations: Vec<Vec3A>,
+    initial_energy: f32,
+    initial_momentum: Vec3A,
+}
+
+impl NBody {
+    pub fn new(particles: Vec<Particle>, integrator: Integrator, time: f32) -> Self {
+        let mut simulation = NBody {
+            particles,
+            integrator,
+            tolerance: 1e-5,
+            time,
+            adaptive_step: MAX_STEP,
+            leapfrog_accelerations: Vec::new(),
+            initial_energy: 0.0,
+            initial_momentum: Vec3A::ZERO,
+        };
+        simulation.initial_energy = simulation.energy();
+        simulation.initial_momentum = simulation.momentum();
+        simulation
+    }
+
+    // Avanza la simulación hasta `time`
+    pub fn advance_to(&mut self, time: f32) {
+        let dt = time - self.time;
+        if dt <= 0.0 {
+            return;
+        }
+
+        if self.integrator != Integrator::Leapfrog {
+            self.leapfrog_accelerations.clear();
+        }
+
+        match self.integrator {
+            Integrator::Leapfrog | Integrator::Rk4 => {
+                let steps = (dt / MAX_STEP).ceil().max(1.0) as usize;
+                let h = dt / steps as f32;
+                for _ in 0..steps {
+                    match self.integrator {
+                        Integrator::Leapfrog => self.leapfrog_step(h),
+                        _ => self.rk4_step(h),
+                    }
+                }
+            }
+            Integrator::Rk45 => {
+                let mut remaining = dt;
+                while remaining > 1e-7 {
+                    let h = self.adaptive_step.min(remaining);
+                    if let Some(taken) = self.rk45_step(h) {
+                        remaining -= taken;
+                    }
+                }
+            }
+        }
+
+        self.time = time;
+    }
+
+    pub fn kinetic_energy(&self) -> f32 {
+        self.particles.iter().map(|p| 0.5 * p.mass * p.velocity.dot(p.velocity)).sum()
+    }
+
+    pub fn potential_energy(&self) -> f32 {
+        let mut energy = 0.0;
+        for (i, a) in self.particles.iter().enumerate() {
+            for b in &self.particles[i + 1..] {
+                let delta = b.position - a.position;
+                let distance = (delta.dot(delta) + SOFTENING * SOFTENING).sqrt();
+                energy -= a.mass * b.mass / distance;
+            }
+        }
+        energy
+    }
+
+    pub fn energy(&self) -> f32 {
+        self.kinetic_energy() + self.potential_energy()
+    }
+
+    pub fn momentum(&self) -> Vec3A {
+        self.particles.iter().fold(Vec3A::ZERO, |sum, p| sum + p.velocity * p.mass)
+    }
+
+    // Deriva relativa de la energía respecto al inicio
+    pub fn energy_drift(&self) -> f32 {
+        let drift = self.energy() - self.initial_energy;
+        if self.initial_energy != 0.0 { drift / self.initial_energy.abs() } else { drift }
+    }
+
+    pub fn momentum_drift(&self) -> f32 {
+        (self.momentum() - self.initial_momentum).length()
+    }
+
+    fn accelerations(&self, positions: impl Fn(usize) -> Vec3A) -> Vec<Vec3A> {
+        let count = self.particles.len();
+        let mut accelerations = vec![Vec3A::ZERO; count];
+        for i in 0..count {
+            for j in i + 1..count {
+                let delta = positions(j) - positions(i);
+                let distance_sqr = delta.dot(delta) + SOFTENING * SOFTENING;
+                let inv_cube = 1.0 / (distance_sqr * distance_sqr.sqrt());
+                accelerations[i] += delta * (self.particles[j].mass * inv_cube);
+                accelerations[j] -= delta * (self.particles[i].mass * inv_cube);
+            }
+        }
+        accelerations
+    }
+
+    fn leapfrog_step(&mut self, h: f32) {
+        if self.leapfrog_accelerations.len() != self.particles.len() {
+            self.leapfrog_accelerations = self.accelerations(|i| self.particles[i].position);
+        }
+        for (particle, acceleration) in self.particles.iter_mut().zip(&self.leapfrog_accelerations) {
+            particle.velocity += *acceleration * (0.5 * h);
+            particle.position += particle.velocity * h;
+        }
+        self.leapfrog_accelerations = self.accelerations(|i| self.particles[i].position);
+        for (particle, acceleration) in self.particles.iter_mut().zip(&self.leapfrog_accelerations) {
+            particle.velocity += *acceleration * (0.5 * h);
+        }
+    }
+
+    fn state(&self) -> Vec<State> {
+        self.particles.iter().map(|p| State { position: p.position, velocity: p.velocity }).collect()
+    }
+
+    fn derivative(&self, state: &[State]) -> Vec<State> {
+        let accelerations = self.accelerations(|i| state[i].position);
+        state
+            .iter()
+            .zip(accelerations)
+            .map(|(s, acceleration)| State { position: s.velocity, velocity: acceleration })
+            .collect()
+    }
+
+    // base + h · Σ coeficiente · k
+    fn combine(base: &[State], h: f32, terms: &[(f32, &[State])]) -> Vec<State> {
+        base.iter()
+            .enumerate()
+            .map(|(i, s)| {
+                let mut result = *s;
+                for &(coefficient, k) in terms {
+                    result.position += k[i].position * (h * coefficient);
+                    result.velocity += k[i].velocity * (h * coefficient);
+                }
+                result
+            })
+            .collect()
+    }
+
+    fn set_state(&mut self, state: &[State]) {
+        for (particle, s) in self.particles.iter_mut().zip(state) {
+            particle.position = s.position;
+            particle.velocity = s.velocity;
+        }
+    }
+
+    fn rk4_step(&mut self, h: f32) {
+        let y = self.state();
+        let k1 = self.derivative(&y);
+        let k2 = self.derivative(&Self::combine(&y, h, &[(0.5, &k1)]));
+        let k3 = self.derivative(&Self::combine(&y, h, &[(0.5, &k2)]));
+        let k4 = self.derivative(&Self::combine(&y, h, &[(1.0, &k3)]));
+        let next = Self::combine(&y, h, &[(1.0 / 6.0, &k1), (1.0 / 3.0, &k2), (1.0 / 3.0, &k3), (1.0 / 6.0, &k4)]);
+        self.set_state(&next);
+    }
+
+    // Un intento de Dormand–Prince; devuelve el paso tomado o None si se rechazó
+    fn rk45_step(&mut self, h: f32) -> Option<f32> {
+        let y = self.state();
+        let k1 = self.derivative(&y);
+        let k2 = self.derivative(&Self::combine(&y, h, &[(1.0 / 5.0, &k1)]));
+        let k3 = self.derivative(&Self::combine(&y, h, &[(3.0 / 40.0, &k1), (9.0 / 40.0, &k2)]));
+        let k4 = self.derivative(&Self::combine(&y, h, &[(44.0 / 45.0, &k1), (-56.0 / 15.0, &k2), (32.0 / 9.0, &k3)]));
+        let k5 = self.derivative(&Self::combine(
+            &y,
+            h,
+            &[(19372.0 / 6561.0, &k1), (-25360.0 / 2187.0, &k2), (64448.0 / 6561.0, &k3), (-212.0 / 729.0, &k4)],
+        ));
+        let k6 = self.derivative(&Self::combine(
+            &y,
+            h,
+            &[(9017.0 / 3168.0, &k1), (-355.0 / 33.0, &k2), (46732.0 / 5247.0, &k3), (49.0 / 176.0, &k4), (-5103.0 / 18656.0, &k5)],
+        ));
+        let fifth = Self::combine(
+            &y,
+            h,
+            &[(35.0 / 384.0, &k1), (500.0 / 1113.0, &k3), (125.0 / 192.0, &k4), (-2187.0 / 6784.0, &k5), (11.0 / 84.0, &k6)],
+        );
+        let k7 = self.derivative(&fifth);
+        let fourth = Self::combine(
+            &y,
+            h,
+            &[
+                (5179.0 / 57600.0, &k1),
+                (7571.0 / 16695.0, &k3),
+                (393.0 / 640.0, &k4),
+                (-92097.0 / 339200.0, &k5),
+                (187.0 / 2100.0, &k6),
+                (1.0 / 40.0, &k7),
+            ],
+        );
+
+        let error = fifth
+            .iter()
+            .zip(&fourth)
+            .map(|(a, b)| (a.position - b.position).length().max((a.velocity - b.velocity).length()))
+            .fold(0.0, f32::max);
+
+        // Ajuste clásico del paso con factor de seguridad 0.9 y límites [0.2, 5]
+        let factor = if error > 0.0 { (0.9 * (self.tolerance / error).powf(0.2)).clamp(0.2, 5.0) } else { 5.0 };
+        self.adaptive_step = (h * factor).clamp(1e-5, 0.1);
+
+        if error <= self.tolerance || h <= 1e-5 {
+            self.set_state(&fifth);
+            Some(h)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cuerpo ligero en órbita elíptica (e = 0.5, a = 4) alrededor de uno pesado, empezando en
+    // el periapsis y con el centro de masa en reposo. El periapsis queda lejos del suavizado,
+    // así que la órbita es casi kepleriana
+    fn two_body(integrator: Integrator) -> NBody {
+        let (heavy, light) = (1.0, 0.01);
+        let mu: f32 = heavy + light;
+        let (a, e) = (4.0_f32, 0.5_f32);
+        let r = Vec3A::new(a * (1.0 - e), 0.0, 0.0);
+        let v = Vec3A::new(0.0, 0.0, -(mu * (2.0 / r.x - 1.0 / a)).sqrt());
+        let particles = vec![
+            Particle { mass: heavy, position: -r * (light / mu), velocity: -v * (light / mu) },
+            Particle { mass: light, position: r * (heavy / mu), velocity: v * (heavy / mu) },
+        ];
+        NBody::new(particles, integrator, 0.0)
+    }
+
+    fn period() -> f32 {
+        std::f32::consts::TAU * (64.0_f32 / 1.01).sqrt()
+    }
+
+    #[test]
+    fn energy_drift_stays_bounded_over_orbits() {
+        for integrator in Integrator::ALL {
+            let mut simulation = two_body(integrator);
+            let mut worst: f32 = 0.0;
+            for step in 1..=200 {
+                simulation.advance_to(period() * 3.0 * step as f32 / 200.0);
+                worst = worst.max(simulation.energy_drift().abs());
+            }
+            assert!(worst < 1e-3, "{}: deriva {worst}", integrator.name());
+            assert!(simulation.momentum_drift() < 1e-4, "{}", integrator.name());
+        }
+    }
+
+    #[test]
+    fn orbit_closes_after_one_period() {
+        for integrator in Integrator::ALL {
+            let mut simulation = two_body(integrator);
+            let start = simulation.particles[1].position - simulation.particles[0].position;
+            simulation.advance_to(period());
+            let end = simulation.particles[1].position - simulation.particles[0].position;
+            // Vuelve al periapsis (a 2 del centro) con un error de fase menor al 5 %
+            assert!((end - start).length() < 0.1, "{}: {:?} != {:?}", integrator.name(), end, start);
+        }
+    }
+
+    #[test]
+    fn rk45_rejects_steps_above_tolerance() {
+        let mut simulation = two_body(Integrator::Rk45);
+        let before = simulation.particles[1];
+
+        // Un paso enorme cerca del periapsis no cumple la tolerancia: se rechaza y se achica
+        assert_eq!(simulation.rk45_step(4.0), None);
+        assert_eq!(simulation.particles[1].position, before.position);
+        assert!(simulation.adaptive_step < 4.0);
+
+        // El paso propuesto termina aceptándose
+        let mut accepted = None;
+        for _ in 0..20 {
+            accepted = simulation.rk45_step(simulation.adaptive_step);
+            if accepted.is_some() {
+                break;
+            }
+        }
+        assert!(accepted.is_some());
+        assert!(simulation.particles[1].position != before.position);
+    }
+
+    #[test]
+    fn switching_integrators_keeps_leapfrog_consistent() {
+        let mut simulation = two_body(Integrator::Leapfrog);
+        simulation.advance_to(1.0);
+        simulation.integrator = Integrator::Rk4;
+        simulation.advance_to(2.0);
+        simulation.integrator = Integrator::Leapfrog;
+        simulation.advance_to(3.0);
+        assert!(simulation.energy_drift().abs() < 1e-3);
+    }
+}
@@ -0,0 +1,420 @@
+use crate::cellular::CellularNoise;
+use crate::simplex::{Simplex, GRAD3};
+use rand::SeedableRng;
+use rand::seq::SliceRandom;
+use glam::Vec3A;
+
+// Tabla de permutación de 256 entradas (duplicada) barajada por semilla
+#[derive(Clone)]
+pub struct Permutation {
+    table: [u8; 512],
+}
+
+impl Permutation {
+    pub fn new(seed: u64) -> Self {
+        let mut values: Vec<u8> = (0..=255).collect();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        values.shuffle(&mut rng);
+
+        let mut table = [0u8; 512];
+        for (i, value) in table.iter_mut().enumerate() {
+            *value = values[i & 255];
+        }
+        Permutation { table }
+    }
+
+    pub fn hash(&self, i: i32) -> usize {
+        self.table[(i & 255) as usize] as usize
+    }
+
+    pub fn hash2(&self, i: i32, j: i32) -> usize {
+        self.hash(i + self.hash(j) as i32)
+    }
+
+    pub fn hash3(&self, i: i32, j: i32, k: i32) -> usize {
+        self.hash(i + self.hash2(j, k) as i32)
+    }
+
+    pub fn hash4(&self, i: i32, j: i32, k: i32, l: i32) -> usize {
+        self.hash(i + self.hash3(j, k, l) as i32)
+    }
+
+    // Valor en [0, 1) por celda; `channel` da valores independientes para la misma celda
+    pub fn unit3(&self, i: i32, j: i32, k: i32, channel: i32) -> f32 {
+        self.hash4(i, j, k, channel) as f32 / 256.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoiseBasis {
+    Value,
+    Gradient,
+    Simplex,
+    Worley,
+}
+
+// Valor del ruido y su gradiente analítico respecto a la posición
+#[derive(Debug, Clone, Copy)]
+pub struct NoiseSample {
+    pub value: f32,
+    pub gradient: Vec3A,
+}
+
+impl NoiseSample {
+    fn zero() -> Self {
+        NoiseSample { value: 0.0, gradient: Vec3A::ZERO }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fractal {
+    pub octaves: usize,
+    pub lacunarity: f32,
+    pub gain: f32,
+}
+
+impl Fractal {
+    pub fn new(octaves: usize) -> Self {
+        Fractal { octaves, lacunarity: 2.0, gain: 0.5 }
+    }
+
+    pub fn with_lacunarity(mut self, lacunarity: f32) -> Self {
+        self.lacunarity = lacunarity;
+        self
+    }
+
+    pub fn with_gain(mut self, gain: f32) -> Self {
+        self.gain = gain;
+        self
+    }
+
+    // Recorre las octavas con (frecuencia, amplitud) y devuelve la suma de amplitudes
+    pub fn octaves(&self, mut visit: impl FnMut(f32, f32)) -> f32 {
+        let mut frequency = 1.0;
+        let mut amplitude = 1.0;
+        let mut total_amplitude = 0.0;
+        for _ in 0..self.octaves.max(1) {
+            visit(frequency, amplitude);
+            total_amplitude += amplitude;
+            frequency *= self.lacunarity;
+            amplitude *= self.gain;
+        }
+        total_amplitude
+    }
+}
+
+// Desplazamientos para obtener canales independientes del mismo ruido
+const OFFSET_A: Vec3A = Vec3A::new(31.4, 17.9, 5.3);
+const OFFSET_B: Vec3A = Vec3A::new(-12.7, 43.1, 27.6);
+const OFFSET_C: Vec3A = Vec3A::new(71.2, -8.4, 53.9);
+
+// Ruido 3D unificado: todas las bases devuelven valores aproximadamente en [-1, 1]
+// junto con su gradiente, y comparten los combinadores fractales.
+pub struct Noise {
+    basis: NoiseBasis,
+    perm: Permutation,
+    simplex: Simplex,
+    cellular: CellularNoise,
+}
+
+impl Noise {
+    pub fn new(basis: NoiseBasis, seed: u64) -> Self {
+        Noise {
+            basis,
+            perm: Permutation::new(seed),
+            simplex: Simplex::new(seed),
+            cellular: CellularNoise::new(seed),
+        }
+    }
+
+    pub fn sample(&self, p: Vec3A) -> f32 {
+        match self.basis {
+            NoiseBasis::Simplex => self.simplex.noise3d(p.x, p.y, p.z),
+            _ => self.sample_grad(p).value,
+        }
+    }
+
+    pub fn sample_grad(&self, p: Vec3A) -> NoiseSample {
+        match self.basis {
+            NoiseBasis::Value => self.value_noise(p),
+            NoiseBasis::Gradient => self.gradient_noise(p),
+            NoiseBasis::Simplex => {
+                let (value, gradient) = self.simplex.noise3d_grad(p.x, p.y, p.z);
+                NoiseSample { value, gradient }
+            }
+            NoiseBasis::Worley => self.worley_noise(p),
+        }
+    }
+
+    pub fn fbm(&self, p: Vec3A, fractal: Fractal) -> f32 {
+        if let NoiseBasis::Simplex = self.basis {
+            return self.simplex.fbm3(p, fractal);
+        }
+        let mut total = 0.0;
+        let amplitude = fractal.octaves(|frequency, amplitude| {
+            total += self.sample(p * frequency) * amplitude;
+        });
+        total / amplitude
+    }
+
+    // El gradiente de cada octava se escala por su frecuencia (regla de la cadena)
+    pub fn fbm_grad(&self, p: Vec3A, fractal: Fractal) -> NoiseSample {
+        let mut total = NoiseSample::zero();
+        let amplitude = fractal.octaves(|frequency, amplitude| {
+            let sample = self.sample_grad(p * frequency);
+            total.value += sample.value * amplitude;
+            total.gradient += sample.gradient * (amplitude * frequency);
+        });
+        NoiseSample {
+            value: total.value / amplitude,
+            gradient: total.gradient / amplitude,
+        }
+    }
+
+    // Nubes "esponjosas": |n| reescalado a [-1, 1]
+    pub fn billow(&self, p: Vec3A, fractal: Fractal) -> f32 {
+        let mut total = 0.0;
+        let amplitude = fractal.octaves(|frequency, amplitude| {
+            total += (self.sample(p * frequency).abs() * 2.0 - 1.0) * amplitude;
+        });
+        total / amplitude
+    }
+
+    // Suma de |n| en [0, 1] (turbulencia de Perlin)
+    pub fn turbulence(&self, p: Vec3A, fractal: Fractal) -> f32 {
+        let mut total = 0.0;
+        let amplitude = fractal.octaves(|frequency, amplitude| {
+            total += self.sample(p * frequency).abs() * amplitude;
+        });
+        total / amplitude
+    }
+
+    // Multifractal con crestas de Musgrave en [0, 1]: cada octava se pondera por la anterior,
+    // así el detalle se concentra en las crestas
+    pub fn ridged(&self, p: Vec3A, fractal: Fractal) -> f32 {
+        const OFFSET: f32 = 1.0;
+        const WEIGHT_GAIN: f32 = 2.0;
+
+        let mut total = 0.0;
+        let mut weight = 1.0;
+        let amplitude = fractal.octaves(|frequency, amplitude| {
+            let ridge = OFFSET - self.sample(p * frequency).abs();
+            let signal = ridge * ridge * weight;
+            weight = (signal * WEIGHT_GAIN).clamp(0.0, 1.0);
+            total += signal * amplitude;
+        });
+        total / amplitude
+    }
+
+    // Deformación del dominio: p + strength · (fbm, fbm, fbm) con canales desplazados
+    pub fn warp(&self, p: Vec3A, strength: f32, fractal: Fractal) -> Vec3A {
+        let offset = Vec3A::new(
+            self.fbm(p + OFFSET_A, fractal),
+            self.fbm(p + OFFSET_B, fractal),
+            self.fbm(p + OFFSET_C, fractal),
+        );
+        p + offset * strength
+    }
+
+    pub fn warped_fbm(&self, p: Vec3A, strength: f32, fractal: Fractal) -> f32 {
+        self.fbm(self.warp(p, strength, fractal), fractal)
+    }
+
+    // Rotacional de un potencial vectorial de fBm: un campo sin divergencia para flujos
+    pub fn curl(&self, p: Vec3A, fractal: Fractal) -> Vec3A {
+        let a = self.fbm_grad(p + OFFSET_A, fractal).gradient;
+        let b = self.fbm_grad(p + OFFSET_B, fractal).gradient;
+        let c = self.fbm_grad(p + OFFSET_C, fractal).gradient;
+        Vec3A::new(c.y - b.z, a.z - c.x, b.x - a.y)
+    }
+
+    // Interpolación trilineal de valores por vértice con curva quíntica
+    fn value_noise(&self, p: Vec3A) -> NoiseSample {
+        let (cell, t) = split(p);
+        let (u, du) = (fade(t), fade_derivative(t));
+        let value = |dx: i32, dy: i32, dz: i32| {
+            self.perm.unit3(cell[0] + dx, cell[1] + dy, cell[2] + dz, 0) * 2.0 - 1.0
+        };
+
+        let a = value(0, 0, 0);
+        let b = value(1, 0, 0);
+        let c = value(0, 1, 0);
+        let d = value(1, 1, 0);
+        let e = value(0, 0, 1);
+        let f = value(1, 0, 1);
+        let g = value(0, 1, 1);
+        let h = value(1, 1, 1);
+
+        let k1 = b - a;
+        let k2 = c - a;
+        let k3 = e - a;
+        let k4 = a - b - c + d;
+        let k5 = a - c - e + g;
+        let k6 = a - b - e + f;
+        let k7 = -a + b + c - d + e - f - g + h;
+
+        NoiseSample {
+            value: a + k1 * u.x + k2 * u.y + k3 * u.z
+                + k4 * u.x * u.y + k5 * u.y * u.z + k6 * u.z * u.x + k7 * u.x * u.y * u.z,
+            gradient: Vec3A::new(
+                du.x * (k1 + k4 * u.y + k6 * u.z + k7 * u.y * u.z),
+                du.y * (k2 + k5 * u.z + k4 * u.x + k7 * u.z * u.x),
+                du.z * (k3 + k6 * u.x + k5 * u.y + k7 * u.x * u.y),
+            ),
+        }
+    }
+
+    // Ruido de gradiente (Perlin mejorado) con derivadas analíticas
+    fn gradient_noise(&self, p: Vec3A) -> NoiseSample {
+        let (cell, f) = split(p);
+        let (u, du) = (fade(f), fade_derivative(f));
+        let corner = |dx: i32, dy: i32, dz: i32| {
+            let g = GRAD3[self.perm.hash3(cell[0] + dx, cell[1] + dy, cell[2] + dz) % 12];
+            let g = Vec3A::new(g[0], g[1], g[2]);
+            let offset = f - Vec3A::new(dx as f32, dy as f32, dz as f32);
+            (g, g.dot(offset))
+        };
+
+        let (ga, va) = corner(0, 0, 0);
+        let (gb, vb) = corner(1, 0, 0);
+        let (gc, vc) = corner(0, 1, 0);
+        let (gd, vd) = corner(1, 1, 0);
+        let (ge, ve) = corner(0, 0, 1);
+        let (gf, vf) = corner(1, 0, 1);
+        let (gg, vg) = corner(0, 1, 1);
+        let (gh, vh) = corner(1, 1, 1);
+
+        let k1 = vb - va;
+        let k2 = vc - va;
+        let k3 = ve - va;
+        let k4 = va - vb - vc + vd;
+        let k5 = va - vc - ve + vg;
+        let k6 = va - vb - ve + vf;
+        let k7 = -va + vb + vc - vd + ve - vf - vg + vh;
+
+        let value = va + k1 * u.x + k2 * u.y + k3 * u.z
+            + k4 * u.x * u.y + k5 * u.y * u.z + k6 * u.z * u.x + k7 * u.x * u.y * u.z;
+
+        let gradient = ga
+            + (gb - ga) * u.x
+            + (gc - ga) * u.y
+            + (ge - ga) * u.z
+            + (ga - gb - gc + gd) * (u.x * u.y)
+            + (ga - gc - ge + gg) * (u.y * u.z)
+            + (ga - gb - ge + gf) * (u.z * u.x)
+            + (-ga + gb + gc - gd + ge - gf - gg + gh) * (u.x * u.y * u.z)
+            + Vec3A::new(
+                du.x * (k1 + k4 * u.y + k6 * u.z + k7 * u.y * u.z),
+                du.y * (k2 + k5 * u.z + k4 * u.x + k7 * u.z * u.x),
+                du.z * (k3 + k6 * u.x + k5 * u.y + k7 * u.x * u.y),
+            );
+
+        NoiseSample { value, gradient }
+    }
+
+    // Distancia al punto característico más cercano (F1) llevada a [-1, 1]
+    fn worley_noise(&self, p: Vec3A) -> NoiseSample {
+        let cell = self.cellular.sample3d(p);
+        let gradient = if cell.f1 > 0.0 { cell.offset * (2.0 / cell.f1) } else { Vec3A::ZERO };
+        NoiseSample { value: cell.f1 * 2.0 - 1.0, gradient }
+    }
+}
+
+fn split(p: Vec3A) -> ([i32; 3], Vec3A) {
+    let floor = Vec3A::new(p.x.floor(), p.y.floor(), p.z.floor());
+    ([floor.x as i32, floor.y as i32, floor.z as i32], p - floor)
+}
+
+// 6t⁵ − 15t⁴ + 10t³ y su derivada 30t²(t − 1)²
+fn fade(t: Vec3A) -> Vec3A {
+    let curve = |t: f32| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+    Vec3A::new(curve(t.x), curve(t.y), curve(t.z))
+}
+
+fn fade_derivative(t: Vec3A) -> Vec3A {
+    let curve = |t: f32| 30.0 * t * t * (t - 1.0) * (t - 1.0);
+    Vec3A::new(curve(t.x), curve(t.y), curve(t.z))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POINTS: [Vec3A; 4] = [
+        Vec3A::new(0.3, 1.7, -2.2),
+        Vec3A::new(4.1, -0.6, 0.9),
+        Vec3A::new(-3.3, 2.45, 5.1),
+        Vec3A::new(10.2, 7.8, -6.4),
+    ];
+
+    fn finite_difference(f: impl Fn(Vec3A) -> f32, p: Vec3A) -> Vec3A {
+        let h = 1e-3;
+        let axis = |d: Vec3A| (f(p + d) - f(p - d)) / (2.0 * h);
+        Vec3A::new(
+            axis(Vec3A::new(h, 0.0, 0.0)),
+            axis(Vec3A::new(0.0, h, 0.0)),
+            axis(Vec3A::new(0.0, 0.0, h)),
+        )
+    }
+
+    // El simplex con radio 0.6 tiene saltos diminutos entre tetraedros: se compara con la
+    // diferencia lateral más cercana, que es la que no cruza el salto
+    #[test]
+    fn analytic_gradient_matches_finite_differences() {
+        let h = 1e-3;
+        let steps = [Vec3A::new(h, 0.0, 0.0), Vec3A::new(0.0, h, 0.0), Vec3A::new(0.0, 0.0, h)];
+        for basis in [NoiseBasis::Value, NoiseBasis::Gradient, NoiseBasis::Simplex] {
+            let noise = Noise::new(basis, 5);
+            for p in POINTS {
+                let gradient = noise.sample_grad(p).gradient;
+                let value = noise.sample(p);
+                for (axis, d) in steps.into_iter().enumerate() {
+                    let analytic = [gradient.x, gradient.y, gradient.z][axis];
+                    let forward = (noise.sample(p + d) - value) / h;
+                    let backward = (value - noise.sample(p - d)) / h;
+                    let error = (analytic - forward).abs().min((analytic - backward).abs());
+                    assert!(error < 0.02, "{basis:?} en {p:?}, eje {axis}: {analytic} vs {forward}/{backward}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fbm_gradient_follows_chain_rule() {
+        for basis in [NoiseBasis::Value, NoiseBasis::Gradient] {
+            let noise = Noise::new(basis, 5);
+            let fractal = Fractal::new(3);
+            for p in POINTS {
+                let expected = finite_difference(|q| noise.fbm(q, fractal), p);
+                let gradient = noise.fbm_grad(p, fractal).gradient;
+                assert!((gradient - expected).length() < 0.02, "{basis:?} en {p:?}: {gradient:?} vs {expected:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn curl_is_divergence_free() {
+        let noise = Noise::new(NoiseBasis::Gradient, 9);
+        let fractal = Fractal::new(2);
+        for p in POINTS {
+            let dx = finite_difference(|q| noise.curl(q, fractal).x, p).x;
+            let dy = finite_difference(|q| noise.curl(q, fractal).y, p).y;
+            let dz = finite_difference(|q| noise.curl(q, fractal).z, p).z;
+            assert!((dx + dy + dz).abs() < 0.05, "divergencia {} en {p:?}", dx + dy + dz);
+        }
+    }
+
+    #[test]
+    fn combinators_stay_in_range() {
+        let noise = Noise::new(NoiseBasis::Gradient, 2);
+        let fractal = Fractal::new(4).with_lacunarity(2.3).with_gain(0.55);
+        for i in 0..500 {
+            let t = i as f32 * 0.173;
+            let p = Vec3A::new(t.sin() * 7.0, t * 0.5, (t * 1.3).cos() * 3.0);
+            assert!((0.0..=1.0).contains(&noise.turbulence(p, fractal)));
+            assert!((0.0..=1.0).contains(&noise.ridged(p, fractal)));
+            assert!((-1.0..=1.0).contains(&noise.billow(p, fractal)));
+        }
+    }
+}
@@ -1,59 +1,181 @@
+use crate::texture::WrapMode;
 use crate::vertex::Vertex;
-use raylib::math::{Vector2, Vector3};
-use tobj;
+use glam::{Vec2, Vec3A};
+use std::path::Path;
+
+// Parámetros de un `newmtl` del archivo MTL
+#[derive(Debug, Clone)]
+pub struct ObjMaterial {
+    pub name: String,
+    pub diffuse: Vec3A,   // Kd
+    pub specular: Vec3A,  // Ks
+    pub shininess: f32,     // Ns
+    pub emissive: Vec3A,  // Ke
+    pub dissolve: f32,      // d (1 = opaco)
+    pub illum: u8,
+    pub diffuse_texture: Option<TextureMap>,  // map_Kd
+}
+
+// Ruta (relativa a la carpeta del OBJ) y opciones de un `map_*`
+#[derive(Debug, Clone)]
+pub struct TextureMap {
+    pub path: String,
+    pub wrap: WrapMode,
+}
+
+impl TextureMap {
+    // tobj deja las opciones (`-clamp on`, `-s 1 1 1`, ...) delante del nombre del archivo
+    fn parse(value: &str, directory: &Path) -> Option<Self> {
+        let mut wrap = WrapMode::Repeat;
+        let mut tokens = value.split_whitespace().peekable();
+
+        while let Some(option) = tokens.next_if(|token| token.starts_with('-')) {
+            if option == "-clamp" && tokens.peek() == Some(&"on") {
+                wrap = WrapMode::Clamp;
+            }
+            while tokens.next_if(|token| is_option_argument(token)).is_some() {}
+        }
+
+        let file: Vec<&str> = tokens.collect();
+        if file.is_empty() {
+            return None;
+        }
+
+        Some(TextureMap {
+            path: directory.join(file.join(" ")).to_string_lossy().into_owned(),
+            wrap,
+        })
+    }
+}
+
+fn is_option_argument(token: &str) -> bool {
+    token == "on" || token == "off" || token.parse::<f32>().is_ok()
+}
+
+impl ObjMaterial {
+    fn from_tobj(material: &tobj::Material, directory: &Path) -> Self {
+        ObjMaterial {
+            name: material.name.clone(),
+            diffuse: Vec3A::new(material.diffuse[0], material.diffuse[1], material.diffuse[2]),
+            specular: Vec3A::new(material.specular[0], material.specular[1], material.specular[2]),
+            shininess: material.shininess,
+            emissive: material
+                .unknown_param
+                .get("Ke")
+                .and_then(|value| parse_vector3(value))
+                .unwrap_or(Vec3A::ZERO),
+            dissolve: material.dissolve,
+            illum: material.illumination_model.unwrap_or(2),
+            diffuse_texture: TextureMap::parse(&material.diffuse_texture, directory),
+        }
+    }
+}
+
+fn parse_vector3(value: &str) -> Option<Vec3A> {
+    let mut components = value.split_whitespace().map(|c| c.parse::<f32>());
+    let x = components.next()?.ok()?;
+    // Un solo valor se repite en los tres canales
+    let y = components.next().map_or(Ok(x), |c| c).ok()?;
+    let z = components.next().map_or(Ok(y), |c| c).ok()?;
+    Some(Vec3A::new(x, y, z))
+}
+
+// Rango de `indices` que comparte el mismo material
+#[derive(Debug, Clone)]
+pub struct SubMesh {
+    pub start: usize,
+    pub count: usize,
+    pub material_id: Option<usize>,
+}
 
 pub struct Obj {
     pub vertices: Vec<Vertex>,
     pub indices: Vec<u32>,
+    pub submeshes: Vec<SubMesh>,
+    pub materials: Vec<ObjMaterial>,
 }
 
 impl Obj {
     pub fn load(path: &str) -> Result<Self, tobj::LoadError> {
-        let (models, _materials) = tobj::load_obj(path, &tobj::GPU_LOAD_OPTIONS)?;
+        let (models, materials) = tobj::load_obj(path, &tobj::GPU_LOAD_OPTIONS)?;
+
+        // Un MTL ausente o inválido no impide cargar la geometría
+        let materials = match materials {
+            Ok(materials) => {
+                let directory = Path::new(path).parent().unwrap_or(Path::new(""));
+                materials.iter().map(|material| ObjMaterial::from_tobj(material, directory)).collect()
+            }
+            Err(tobj::LoadError::OpenFileFailed) => Vec::new(),
+            Err(err) => {
+                eprintln!("No se pudieron cargar los materiales de {path}: {err}");
+                Vec::new()
+            }
+        };
 
         let mut vertices = Vec::new();
         let mut indices = Vec::new();
+        let mut submeshes = Vec::new();
 
         for model in models {
             let mesh = &model.mesh;
             let num_vertices = mesh.positions.len() / 3;
+            let base_index = vertices.len() as u32;
 
             for i in 0..num_vertices {
                 let x = mesh.positions[i * 3];
                 let y = mesh.positions[i * 3 + 1];
                 let z = mesh.positions[i * 3 + 2];
-                let position = Vector3::new(x, -y, z);
+                let position = Vec3A::new(x, -y, z);
 
                 let normal = if !mesh.normals.is_empty() {
                     let nx = mesh.normals[i * 3];
                     let ny = mesh.normals[i * 3 + 1];
                     let nz = mesh.normals[i * 3 + 2];
-                    Vector3::new(nx, ny, nz)
+                    Vec3A::new(nx, ny, nz)
                 } else {
-                    Vector3::zero()
+                    Vec3A::ZERO
                 };
 
                 let tex_coords = if !mesh.texcoords.is_empty() {
                     let u = mesh.texcoords[i * 2];
                     let v = mesh.texcoords[i * 2 + 1];
-                    Vector2::new(u, v)
+                    Vec2::new(u, v)
                 } else {
-                    Vector2::zero()
+                    Vec2::ZERO
                 };
 
-                vertices.push(Vertex::new(position, normal, tex_coords));
+                let mut vertex = Vertex::new(position, normal, tex_coords);
+                if !mesh.vertex_color.is_empty() {
+                    vertex.color = Vec3A::new(
+                        mesh.vertex_color[i * 3],
+                        mesh.vertex_color[i * 3 + 1],
+                        mesh.vertex_color[i * 3 + 2],
+                    );
+                }
+
+                vertices.push(vertex);
             }
-            indices.extend_from_slice(&mesh.indices);
+
+            // Los índices de cada malla son locales a sus propios vértices
+            submeshes.push(SubMesh {
+                start: indices.len(),
+                count: mesh.indices.len(),
+                material_id: mesh.material_id,
+            });
+            indices.extend(mesh.indices.iter().map(|&index| index + base_index));
         }
 
-        Ok(Obj { vertices, indices })
+        Ok(Obj { vertices, indices, submeshes, materials })
     }
 
-    pub fn get_vertex_array(&self) -> Vec<Vertex> {
-        let mut vertex_array = Vec::new();
-        for &index in &self.indices {
-            vertex_array.push(self.vertices[index as usize].clone());
-        }
-        vertex_array
+    pub fn submesh_vertex_array(&self, submesh: &SubMesh) -> Vec<Vertex> {
+        self.indices[submesh.start..submesh.start + submesh.count]
+            .iter()
+            .map(|&index| self.vertices[index as usize].clone())
+            .collect()
+    }
+
+    pub fn material(&self, submesh: &SubMesh) -> Option<&ObjMaterial> {
+        self.materials.get(submesh.material_id?)
     }
 }
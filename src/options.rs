@@ -0,0 +1,159 @@
+use crate::ephemeris;
+use crate::fragment::SphereUvMode;
+use crate::nbody::Integrator;
+use crate::planet::PlanetClass;
+use crate::scene::DEFAULT_SCENE_PATH;
+use crate::varyings::Varying;
+use std::process;
+
+pub const DEFAULT_RECORDING_PATH: &str = "recording.gif";
+
+pub struct Options {
+    pub headless: bool,
+    pub record_path: Option<String>,
+    pub record_seconds: Option<f32>,
+    pub record_fps: u32,
+    pub record_downscale: u32,
+    pub dither: bool,
+    pub sphere_uv_mode: SphereUvMode,
+    pub debug_varying: Option<Varying>,
+    pub scene_path: String,
+    pub planets: Vec<(PlanetClass, u64)>,
+    pub nbody: bool,
+    pub integrator: Integrator,
+    pub paths: bool,
+    pub profile: bool,
+    // Día juliano con el que arranca el modo fecha
+    pub date: Option<f64>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            headless: false,
+            record_path: None,
+            record_seconds: None,
+            record_fps: 30,
+            record_downscale: 1,
+            dither: true,
+            sphere_uv_mode: SphereUvMode::default(),
+            debug_varying: None,
+            scene_path: DEFAULT_SCENE_PATH.to_string(),
+            planets: Vec::new(),
+            nbody: false,
+            integrator: Integrator::default(),
+            paths: true,
+            profile: false,
+            date: None,
+        }
+    }
+}
+
+const USAGE: &str = "\
+Uso: lab4 [opciones]
+
+  --headless              Renderiza sin ventana (requiere --record)
+  --record <archivo>      Graba la simulación en .gif o .png (APNG)
+  --seconds <n>           Segundos de simulación a grabar (por defecto 5 en --headless)
+  --fps <n>               Cuadros por segundo de la grabación (por defecto 30)
+  --downscale <n>         Reduce la resolución de la grabación por un factor entero
+  --no-dither             Desactiva el tramado Floyd–Steinberg del GIF
+  --vertex-uv             Calcula las UV esféricas por vértice (costura visible en ±π)
+  --varying <nombre>      Pinta un interpolante en lugar de los materiales: uv, sphere_uv,
+                          color, tangent, object_position o custom0..custom5
+  --scene <archivo>       Escena TOML a cargar (por defecto assets/scenes/solar_system.toml)
+  --planet <clase[:semilla]>
+                          Agrega un mundo generado (rocky, desert, ice, gas_giant,
+                          ice_giant, lava, ocean); se puede repetir
+  --nbody                 Arranca en modo simulación gravitatoria (N alterna)
+  --integrator <nombre>   Integrador de la simulación: leapfrog, rk4 o rk45
+  --no-paths              Oculta órbitas y estelas al arrancar (O alterna)
+  --profile               Muestra el tiempo de cada etapa del cuadro (P alterna)
+  --date <fecha>          Arranca en modo fecha con posiciones reales: AAAA-MM-DD,
+                          AAAA-MM-DDTHH:MM (UTC) o now; D alterna y J salta a una fecha
+  -h, --help              Muestra esta ayuda";
+
+impl Options {
+    pub fn from_args() -> Self {
+        match Self::parse(std::env::args().skip(1)) {
+            Ok(options) => options,
+            Err(message) => {
+                eprintln!("{message}\n\n{USAGE}");
+                process::exit(2);
+            }
+        }
+    }
+
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => options.headless = true,
+                "--record" => options.record_path = Some(value(&arg, args.next())?),
+                "--seconds" => options.record_seconds = Some(parse_number(&arg, args.next())?),
+                "--fps" => options.record_fps = parse_number(&arg, args.next())?,
+                "--downscale" => options.record_downscale = parse_number(&arg, args.next())?,
+                "--no-dither" => options.dither = false,
+                "--vertex-uv" => options.sphere_uv_mode = SphereUvMode::PerVertex,
+                "--varying" => {
+                    let name = value(&arg, args.next())?;
+                    options.debug_varying = Some(Varying::from_name(&name).ok_or_else(|| format!("interpolante desconocido: {name}"))?);
+                }
+                "--scene" => options.scene_path = value(&arg, args.next())?,
+                "--planet" => {
+                    let planet = parse_planet(&value(&arg, args.next())?, options.planets.len() as u64)?;
+                    options.planets.push(planet);
+                }
+                "--nbody" => options.nbody = true,
+                "--no-paths" => options.paths = false,
+                "--profile" => options.profile = true,
+                "--date" => options.date = Some(ephemeris::parse_date(&value(&arg, args.next())?)?),
+                "--integrator" => {
+                    let name = value(&arg, args.next())?;
+                    options.integrator = Integrator::from_name(&name).ok_or_else(|| format!("integrador desconocido: {name}"))?;
+                }
+                "-h" | "--help" => {
+                    println!("{USAGE}");
+                    process::exit(0);
+                }
+                _ => return Err(format!("opción desconocida: {arg}")),
+            }
+        }
+
+        if options.headless && options.record_path.is_none() {
+            return Err("--headless necesita --record <archivo>".to_string());
+        }
+        if options.nbody && options.date.is_some() {
+            return Err("--nbody y --date no se pueden combinar".to_string());
+        }
+        if options.record_fps == 0 || options.record_downscale == 0 {
+            return Err("--fps y --downscale deben ser mayores que cero".to_string());
+        }
+
+        Ok(options)
+    }
+}
+
+fn value(flag: &str, next: Option<String>) -> Result<String, String> {
+    next.ok_or_else(|| format!("falta el valor de {flag}"))
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, next: Option<String>) -> Result<T, String> {
+    let raw = value(flag, next)?;
+    raw.parse()
+        .map_err(|_| format!("valor inválido para {flag}: {raw}"))
+}
+
+// "clase" o "clase:semilla"; sin semilla se usa la posición del mundo en la lista
+fn parse_planet(raw: &str, default_seed: u64) -> Result<(PlanetClass, u64), String> {
+    let (name, seed) = match raw.split_once(':') {
+        Some((name, seed)) => {
+            let seed = seed.parse().map_err(|_| format!("semilla inválida para --planet: {seed}"))?;
+            (name, seed)
+        }
+        None => (raw, default_seed),
+    };
+    let class = PlanetClass::from_name(name).ok_or_else(|| format!("clase de planeta desconocida: {name}"))?;
+    Ok((class, seed))
+}
@@ -0,0 +1,201 @@
+use glam::{Vec2, Vec3A};
+use std::f32::consts::{PI, TAU};
+
+// Elementos orbitales clásicos, con los ángulos en radianes. El plano de referencia es XZ
+// (la "eclíptica"), con el norte hacia +Y y el nodo ascendente medido desde +X hacia -Z: así
+// una órbita directa gira en el mismo sentido que los cuerpos alrededor de +Y.
+#[derive(Debug, Clone, Copy)]
+pub struct OrbitalElements {
+    pub semi_major_axis: f32,
+    pub eccentricity: f32,
+    pub inclination: f32,
+    pub ascending_node: f32,
+    pub argument_of_periapsis: f32,
+    // Anomalía media en la época (tiempo 0)
+    pub mean_anomaly: f32,
+}
+
+impl OrbitalElements {
+    pub fn apoapsis(&self) -> f32 {
+        self.semi_major_axis * (1.0 + self.eccentricity)
+    }
+
+    // Posición relativa al cuerpo central con la anomalía media de la época
+    pub fn position(&self) -> Vec3A {
+        self.position_at(solve_kepler(self.mean_anomaly, self.eccentricity))
+    }
+
+    // Elipse completa muestreada en la anomalía excéntrica (más puntos cerca del periapsis
+    // que un muestreo en el tiempo)
+    pub fn path(&self, segments: usize) -> Vec<Vec3A> {
+        (0..segments).map(|i| self.position_at(TAU * i as f32 / segments as f32)).collect()
+    }
+
+    fn position_at(&self, eccentric: f32) -> Vec3A {
+        let e = self.eccentricity;
+        let half = eccentric * 0.5;
+        let nu = 2.0 * ((1.0 + e).sqrt() * half.sin()).atan2((1.0 - e).sqrt() * half.cos());
+        let r = self.semi_major_axis * (1.0 - e * eccentric.cos());
+        self.perifocal_to_reference(Vec2::new(r * nu.cos(), r * nu.sin()))
+    }
+
+    // Del plano orbital (x hacia el periapsis) al de referencia: rota ω, inclina i y gira Ω
+    fn perifocal_to_reference(&self, perifocal: Vec2) -> Vec3A {
+        let (sin_o, cos_o) = self.ascending_node.sin_cos();
+        let (sin_w, cos_w) = self.argument_of_periapsis.sin_cos();
+        let (sin_i, cos_i) = self.inclination.sin_cos();
+
+        let x = perifocal.x * cos_w - perifocal.y * sin_w;
+        let y = perifocal.x * sin_w + perifocal.y * cos_w;
+
+        // (x, y, z) eclípticos van a (x, z, -y) de la escena
+        Vec3A::new(
+            x * cos_o - y * sin_o * cos_i,
+            y * sin_i,
+            -(x * sin_o + y * cos_o * cos_i),
+        )
+    }
+}
+
+// Órbita elíptica alrededor de un cuerpo central con parámetro gravitacional `mu` (G·M)
+#[derive(Debug, Clone, Copy)]
+pub struct Orbit {
+    pub elements: OrbitalElements,
+    pub mu: f32,
+    pub period: f32,
+}
+
+impl Orbit {
+    // Tercera ley de Kepler: T = 2π √(a³ / μ)
+    pub fn new(elements: OrbitalElements, mu: f32) -> Self {
+        let a = elements.semi_major_axis;
+        Orbit { elements, mu, period: TAU * (a * a * a / mu).sqrt() }
+    }
+
+    // Periodo impuesto; μ se deduce de la misma ley para que las velocidades sean coherentes
+    pub fn with_period(elements: OrbitalElements, period: f32) -> Self {
+        let a = elements.semi_major_axis;
+        Orbit { elements, mu: TAU * TAU * a * a * a / (period * period), period }
+    }
+
+    pub fn mean_motion(&self) -> f32 {
+        TAU / self.period
+    }
+
+    pub fn mean_anomaly(&self, time: f32) -> f32 {
+        (self.elements.mean_anomaly + self.mean_motion() * time).rem_euclid(TAU)
+    }
+
+    pub fn eccentric_anomaly(&self, time: f32) -> f32 {
+        solve_kepler(self.mean_anomaly(time), self.elements.eccentricity)
+    }
+
+    pub fn true_anomaly(&self, time: f32) -> f32 {
+        let e = self.elements.eccentricity;
+        let half = self.eccentric_anomaly(time) * 0.5;
+        2.0 * ((1.0 + e).sqrt() * half.sin()).atan2((1.0 - e).sqrt() * half.cos())
+    }
+
+    // Posición relativa al cuerpo central
+    pub fn position(&self, time: f32) -> Vec3A {
+        self.elements.position_at(self.eccentric_anomaly(time))
+    }
+
+    pub fn path(&self, segments: usize) -> Vec<Vec3A> {
+        self.elements.path(segments)
+    }
+
+    // Velocidad relativa al cuerpo central (sirve de condición inicial para la simulación)
+    pub fn velocity(&self, time: f32) -> Vec3A {
+        let e = self.elements.eccentricity;
+        let nu = self.true_anomaly(time);
+        let p = self.elements.semi_major_axis * (1.0 - e * e);
+        let speed = (self.mu / p).sqrt();
+        self.elements.perifocal_to_reference(Vec2::new(-speed * nu.sin(), speed * (e + nu.cos())))
+    }
+}
+
+// Resuelve M = E - e·sin(E) con Newton-Raphson. Para excentricidades altas se parte de π,
+// que converge aunque M esté cerca del periapsis.
+pub fn solve_kepler(mean_anomaly: f32, eccentricity: f32) -> f32 {
+    let m = mean_anomaly.rem_euclid(TAU);
+    let mut e_anomaly = if eccentricity < 0.8 { m } else { PI };
+
+    for _ in 0..16 {
+        let f = e_anomaly - eccentricity * e_anomaly.sin() - m;
+        let step = f / (1.0 - eccentricity * e_anomaly.cos());
+        e_anomaly -= step;
+        if step.abs() < 1e-6 {
+            break;
+        }
+    }
+
+    e_anomaly
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn elements(eccentricity: f32) -> OrbitalElements {
+        OrbitalElements {
+            semi_major_axis: 3.0,
+            eccentricity,
+            inclination: 0.4,
+            ascending_node: 1.1,
+            argument_of_periapsis: 0.7,
+            mean_anomaly: 0.0,
+        }
+    }
+
+    #[test]
+    fn kepler_residual_is_small() {
+        for eccentricity in [0.0, 0.5, 0.95] {
+            for step in 0..64 {
+                let m = TAU * step as f32 / 64.0;
+                let e_anomaly = solve_kepler(m, eccentricity);
+                let residual = e_anomaly - eccentricity * e_anomaly.sin() - m;
+                let residual = (residual + PI).rem_euclid(TAU) - PI;
+                assert!(residual.abs() < 1e-5, "e = {eccentricity}, M = {m}: {residual}");
+            }
+        }
+    }
+
+    #[test]
+    fn positions_at_periapsis_and_apoapsis() {
+        for eccentricity in [0.0, 0.5, 0.95] {
+            let orbit = Orbit::with_period(elements(eccentricity), 10.0);
+            let a = orbit.elements.semi_major_axis;
+
+            // La época es el periapsis (M = 0) y medio periodo después está el apoapsis
+            let periapsis = orbit.position(0.0);
+            let apoapsis = orbit.position(orbit.period * 0.5);
+            assert!((periapsis.length() - a * (1.0 - eccentricity)).abs() < 1e-4);
+            assert!((apoapsis.length() - orbit.elements.apoapsis()).abs() < 1e-4);
+            // Quedan en lados opuestos del foco
+            assert!(periapsis.normalize().dot(apoapsis.normalize()) < -0.999);
+            assert!((orbit.position(orbit.period) - periapsis).length() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn period_follows_keplers_third_law() {
+        let orbit = Orbit::new(elements(0.2), 4.0);
+        // T = 2π √(a³ / μ) con a = 3, μ = 4
+        assert!((orbit.period - TAU * (27.0_f32 / 4.0).sqrt()).abs() < 1e-4);
+
+        // Fijar el periodo deduce el mismo μ
+        let fixed = Orbit::with_period(elements(0.2), orbit.period);
+        assert!((fixed.mu - 4.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn velocity_matches_vis_viva() {
+        let orbit = Orbit::new(elements(0.5), 2.0);
+        for time in [0.0, 1.0, 2.5, 7.0] {
+            let r = orbit.position(time).length();
+            let expected = (orbit.mu * (2.0 / r - 1.0 / orbit.elements.semi_major_axis)).sqrt();
+            assert!((orbit.velocity(time).length() - expected).abs() < 1e-3, "t = {time}");
+        }
+    }
+}
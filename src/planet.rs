@@ -0,0 +1,260 @@
+use crate::gradient::{linear_to_oklab, oklab_to_linear, ColorRamp, ColorSpace};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use glam::Vec3A;
+use std::f32::consts::PI;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanetClass {
+    Rocky,
+    Desert,
+    Ice,
+    GasGiant,
+    IceGiant,
+    Lava,
+    Ocean,
+}
+
+impl PlanetClass {
+    pub const ALL: [PlanetClass; 7] = [
+        PlanetClass::Rocky,
+        PlanetClass::Desert,
+        PlanetClass::Ice,
+        PlanetClass::GasGiant,
+        PlanetClass::IceGiant,
+        PlanetClass::Lava,
+        PlanetClass::Ocean,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PlanetClass::Rocky => "rocky",
+            PlanetClass::Desert => "desert",
+            PlanetClass::Ice => "ice",
+            PlanetClass::GasGiant => "gas_giant",
+            PlanetClass::IceGiant => "ice_giant",
+            PlanetClass::Lava => "lava",
+            PlanetClass::Ocean => "ocean",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|class| class.name() == name)
+    }
+
+    // Los gigantes se pintan por bandas de latitud; el resto, por altura del terreno
+    pub fn is_banded(self) -> bool {
+        matches!(self, PlanetClass::GasGiant | PlanetClass::IceGiant)
+    }
+
+    // Colores de referencia de menor a mayor altura (o de banda a banda en los gigantes)
+    fn base_colors(self) -> [Vec3A; 4] {
+        match self {
+            PlanetClass::Rocky => [
+                Vec3A::new(0.25, 0.22, 0.20),
+                Vec3A::new(0.45, 0.40, 0.35),
+                Vec3A::new(0.60, 0.55, 0.50),
+                Vec3A::new(0.75, 0.72, 0.68),
+            ],
+            PlanetClass::Desert => [
+                Vec3A::new(0.55, 0.35, 0.20),
+                Vec3A::new(0.75, 0.55, 0.30),
+                Vec3A::new(0.90, 0.75, 0.50),
+                Vec3A::new(0.95, 0.85, 0.65),
+            ],
+            PlanetClass::Ice => [
+                Vec3A::new(0.55, 0.65, 0.75),
+                Vec3A::new(0.75, 0.85, 0.92),
+                Vec3A::new(0.90, 0.95, 1.00),
+                Vec3A::new(1.00, 1.00, 1.00),
+            ],
+            PlanetClass::GasGiant => [
+                Vec3A::new(0.60, 0.45, 0.30),
+                Vec3A::new(0.85, 0.70, 0.50),
+                Vec3A::new(0.95, 0.90, 0.80),
+                Vec3A::new(0.70, 0.50, 0.35),
+            ],
+            PlanetClass::IceGiant => [
+                Vec3A::new(0.30, 0.50, 0.80),
+                Vec3A::new(0.45, 0.70, 0.90),
+                Vec3A::new(0.60, 0.85, 0.95),
+                Vec3A::new(0.35, 0.60, 0.85),
+            ],
+            PlanetClass::Lava => [
+                Vec3A::new(0.08, 0.05, 0.05),
+                Vec3A::new(0.20, 0.10, 0.08),
+                Vec3A::new(0.35, 0.20, 0.15),
+                Vec3A::new(0.45, 0.30, 0.25),
+            ],
+            PlanetClass::Ocean => [
+                Vec3A::new(0.02, 0.08, 0.30),
+                Vec3A::new(0.05, 0.30, 0.55),
+                Vec3A::new(0.20, 0.50, 0.20),
+                Vec3A::new(0.50, 0.45, 0.40),
+            ],
+        }
+    }
+
+    // Cuánto puede girar el tono de la paleta (radianes en el plano a-b de Oklab)
+    fn hue_range(self) -> f32 {
+        match self {
+            PlanetClass::GasGiant | PlanetClass::IceGiant => 0.8,
+            PlanetClass::Rocky | PlanetClass::Ice => 0.5,
+            PlanetClass::Desert | PlanetClass::Lava | PlanetClass::Ocean => 0.2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Storm {
+    pub center: Vec3A,  // dirección sobre la esfera unitaria
+    pub radius: f32,      // radio angular en radianes
+    pub color: Vec3A,
+}
+
+#[derive(Debug, Clone)]
+pub struct RingParams {
+    pub ramp: ColorRamp,  // de la orilla interior (0) a la exterior (1)
+    pub opacity: f32,
+    pub gaps: Vec<f32>,   // posiciones de las divisiones en [0, 1]
+}
+
+// Conjunto completo de parámetros de un mundo; lo consume `PlanetMaterial`
+#[derive(Debug, Clone)]
+pub struct PlanetParams {
+    pub class: PlanetClass,
+    pub seed: u64,
+    pub radius: f32,
+    pub palette: ColorRamp,
+    pub terrain_scale: f32,
+    pub band_count: u32,
+    pub band_turbulence: f32,
+    pub storms: Vec<Storm>,
+    pub polar_cap: f32,  // fracción de latitud que cubre cada casquete (0 = sin casquetes)
+    pub cap_color: Vec3A,
+    pub crater_density: f32,
+    pub sea_level: f32,
+    pub glow: Vec3A,  // emisión de las grietas de lava
+    pub ring: Option<RingParams>,
+}
+
+impl PlanetParams {
+    // La misma semilla y clase dan siempre el mismo mundo
+    pub fn generate(seed: u64, class: PlanetClass) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed ^ (class as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+
+        let hue = rng.gen_range(-1.0..1.0) * class.hue_range();
+        let colors = class.base_colors().map(|color| vary_color(&mut rng, color, hue));
+        let sea_level = if class == PlanetClass::Ocean { rng.gen_range(0.5..0.65) } else { 0.0 };
+        let palette = build_palette(class, &colors, sea_level);
+
+        let band_count = if class.is_banded() { rng.gen_range(4..=12) } else { 0 };
+        let storm_count = match class {
+            PlanetClass::GasGiant => rng.gen_range(1..=3),
+            PlanetClass::IceGiant => rng.gen_range(0..=2),
+            _ => 0,
+        };
+        let storms = (0..storm_count)
+            .map(|_| {
+                let latitude = rng.gen_range(-0.6f32..0.6);
+                let longitude = rng.gen_range(0.0..2.0 * PI);
+                let tint = palette.sample(rng.gen_range(0.0..1.0));
+                let hue = rng.gen_range(-0.6..0.6);
+                Storm {
+                    center: Vec3A::new(
+                        latitude.cos() * longitude.sin(),
+                        latitude.sin(),
+                        latitude.cos() * longitude.cos(),
+                    ),
+                    radius: rng.gen_range(0.12..0.3),
+                    color: vary_color(&mut rng, tint * 1.15, hue),
+                }
+            })
+            .collect();
+
+        let polar_cap = match class {
+            PlanetClass::Rocky if rng.gen_bool(0.5) => rng.gen_range(0.05..0.25),
+            PlanetClass::Desert if rng.gen_bool(0.3) => 0.1,
+            PlanetClass::Ice => rng.gen_range(0.35..0.5),
+            PlanetClass::Ocean => rng.gen_range(0.1..0.25),
+            _ => 0.0,
+        };
+        let crater_density = match class {
+            PlanetClass::Rocky => rng.gen_range(0.3..0.7),
+            PlanetClass::Desert => rng.gen_range(0.1..0.3),
+            PlanetClass::Ice => rng.gen_range(0.05..0.2),
+            PlanetClass::Lava => rng.gen_range(0.0..0.1),
+            _ => 0.0,
+        };
+        let glow = if class == PlanetClass::Lava {
+            vary_color(&mut rng, Vec3A::new(1.0, 0.35, 0.05), 0.15)
+        } else {
+            Vec3A::ZERO
+        };
+
+        let ring_chance = match class {
+            PlanetClass::GasGiant => 0.6,
+            PlanetClass::IceGiant => 0.4,
+            PlanetClass::Ice => 0.1,
+            _ => 0.05,
+        };
+        let ring = rng.gen_bool(ring_chance).then(|| {
+            let inner = vary_color(&mut rng, colors[1] * 0.9, 0.3);
+            let outer = vary_color(&mut rng, colors[2], 0.3);
+            RingParams {
+                ramp: ColorRamp::new(ColorSpace::Oklab)
+                    .with_stop(0.0, inner * 0.7)
+                    .with_stop(0.4, inner)
+                    .with_stop(1.0, outer),
+                opacity: rng.gen_range(0.5..0.9),
+                gaps: (0..rng.gen_range(1..=3)).map(|_| rng.gen_range(0.2..0.85)).collect(),
+            }
+        });
+
+        let radius = if class.is_banded() { rng.gen_range(0.9..1.2) } else { rng.gen_range(0.5..0.8) };
+
+        PlanetParams {
+            class,
+            seed,
+            radius,
+            palette,
+            terrain_scale: rng.gen_range(1.5..3.5),
+            band_count,
+            band_turbulence: rng.gen_range(0.02..0.08),
+            storms,
+            polar_cap,
+            cap_color: vary_color(&mut rng, Vec3A::new(0.92, 0.94, 0.97), 0.1),
+            crater_density,
+            sea_level,
+            glow,
+            ring,
+        }
+    }
+}
+
+fn build_palette(class: PlanetClass, colors: &[Vec3A; 4], sea_level: f32) -> ColorRamp {
+    let ramp = ColorRamp::new(ColorSpace::Oklab);
+    if class == PlanetClass::Ocean {
+        let sand = Vec3A::new(0.80, 0.75, 0.50);
+        return ramp
+            .with_stop(0.0, colors[0])
+            .with_stop(sea_level - 0.03, colors[1])
+            .with_stop(sea_level, sand)
+            .with_stop(sea_level + 0.04, colors[2])
+            .with_stop(1.0, colors[3]);
+    }
+    ramp.with_stop(0.0, colors[0])
+        .with_stop(0.35, colors[1])
+        .with_stop(0.65, colors[2])
+        .with_stop(1.0, colors[3])
+}
+
+// Gira el tono y varía un poco la luminosidad en Oklab
+fn vary_color(rng: &mut StdRng, color: Vec3A, hue: f32) -> Vec3A {
+    let lab = linear_to_oklab(color);
+    let (sin, cos) = hue.sin_cos();
+    let lightness = lab.x * rng.gen_range(0.92..1.08);
+    let varied = Vec3A::new(lightness, lab.y * cos - lab.z * sin, lab.y * sin + lab.z * cos);
+    let rgb = oklab_to_linear(varied);
+    Vec3A::new(rgb.x.max(0.0), rgb.y.max(0.0), rgb.z.max(0.0))
+}
@@ -0,0 +1,128 @@
+use std::time::{Duration, Instant};
+
+// Etapas del cuadro que se miden por separado
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Update,
+    Vertex,
+    Raster,
+    Fragment,
+    Paths,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 5] = [Stage::Update, Stage::Vertex, Stage::Raster, Stage::Fragment, Stage::Paths];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::Update => "escena",
+            Stage::Vertex => "vértices",
+            Stage::Raster => "rasterizado",
+            Stage::Fragment => "fragmentos",
+            Stage::Paths => "trayectorias",
+        }
+    }
+}
+
+// Peso del último cuadro en la media móvil que se muestra en pantalla
+const SMOOTHING: f64 = 0.1;
+
+// Acumula el tiempo de cada etapa: el total desde el inicio y una media móvil por cuadro
+pub struct Profiler {
+    frame: [Duration; Stage::ALL.len()],
+    total: [Duration; Stage::ALL.len()],
+    recent: [f64; Stage::ALL.len()],
+    frames: u32,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Profiler {
+            frame: [Duration::ZERO; Stage::ALL.len()],
+            total: [Duration::ZERO; Stage::ALL.len()],
+            recent: [0.0; Stage::ALL.len()],
+            frames: 0,
+        }
+    }
+
+    pub fn time<T>(&mut self, stage: Stage, work: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = work();
+        self.frame[stage as usize] += start.elapsed();
+        result
+    }
+
+    pub fn end_frame(&mut self) {
+        for (i, spent) in self.frame.iter_mut().enumerate() {
+            let ms = spent.as_secs_f64() * 1000.0;
+            self.recent[i] = if self.frames == 0 { ms } else { self.recent[i] + (ms - self.recent[i]) * SMOOTHING };
+            self.total[i] += *spent;
+            *spent = Duration::ZERO;
+        }
+        self.frames += 1;
+    }
+
+    // Milisegundos promedio por cuadro desde el inicio
+    pub fn average(&self, stage: Stage) -> f64 {
+        if self.frames == 0 {
+            return 0.0;
+        }
+        self.total[stage as usize].as_secs_f64() * 1000.0 / self.frames as f64
+    }
+
+    // Líneas para la pantalla, con la media móvil de los últimos cuadros
+    pub fn overlay(&self) -> Vec<String> {
+        let total: f64 = self.recent.iter().sum();
+        let mut lines = vec![format!("Cuadro {total:.1} ms - [P] ocultar")];
+        lines.extend(Stage::ALL.iter().map(|&stage| format!("  {:<13}{:6.2} ms", stage.name(), self.recent[stage as usize])));
+        lines
+    }
+
+    // Resumen para la terminal con los promedios de toda la ejecución
+    pub fn report(&self) -> String {
+        let total: f64 = Stage::ALL.iter().map(|&stage| self.average(stage)).sum();
+        let mut report = format!("Perfil ({} cuadros, {total:.2} ms por cuadro):", self.frames);
+        for stage in Stage::ALL {
+            let average = self.average(stage);
+            let share = if total > 0.0 { average / total * 100.0 } else { 0.0 };
+            report.push_str(&format!("\n  {:<13}{average:8.3} ms  {share:5.1} %", stage.name()));
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(profiler: &mut Profiler, raster_ms: u64, fragment_ms: u64) {
+        profiler.frame[Stage::Raster as usize] = Duration::from_millis(raster_ms);
+        profiler.frame[Stage::Fragment as usize] = Duration::from_millis(fragment_ms);
+        profiler.end_frame();
+    }
+
+    #[test]
+    fn averages_over_all_frames_and_smooths_the_overlay() {
+        let mut profiler = Profiler::new();
+        assert_eq!(profiler.average(Stage::Raster), 0.0);
+        frame(&mut profiler, 10, 30);
+        frame(&mut profiler, 20, 30);
+
+        assert!((profiler.average(Stage::Raster) - 15.0).abs() < 1e-9);
+        assert!((profiler.average(Stage::Fragment) - 30.0).abs() < 1e-9);
+        // El primer cuadro inicia la media móvil; el segundo pesa SMOOTHING
+        assert!((profiler.recent[Stage::Raster as usize] - 11.0).abs() < 1e-9);
+        assert!(profiler.report().starts_with("Perfil (2 cuadros, 45.00 ms por cuadro):"));
+    }
+
+    #[test]
+    fn time_accumulates_into_the_current_frame() {
+        let mut profiler = Profiler::new();
+        let value = profiler.time(Stage::Update, || 7);
+        assert_eq!(value, 7);
+        profiler.time(Stage::Update, || std::thread::sleep(Duration::from_millis(2)));
+        assert!(profiler.frame[Stage::Update as usize] >= Duration::from_millis(2));
+        profiler.end_frame();
+        assert_eq!(profiler.frame[Stage::Update as usize], Duration::ZERO);
+    }
+}
@@ -0,0 +1,369 @@
+use crate::framebuffer::Framebuffer;
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+
+const PALETTE_SIZE: usize = 256;
+const MAX_PALETTE_SAMPLES: usize = 250_000;
+// Los cuadros quedan en memoria hasta el final (el GIF usa una paleta común y el APNG declara
+// la cantidad de cuadros en su cabecera), así que la grabación tiene un tope
+const MAX_RECORDING_BYTES: usize = 1 << 30;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecordFormat {
+    Gif,
+    Apng,
+}
+
+impl RecordFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "gif" => Some(RecordFormat::Gif),
+            "png" | "apng" => Some(RecordFormat::Apng),
+            _ => None,
+        }
+    }
+}
+
+// Guarda los cuadros del framebuffer (RGB, opcionalmente reducidos) y los
+// codifica al terminar, para poder calcular una paleta común a toda la animación.
+// `capture` falla al pasar de MAX_RECORDING_BYTES.
+pub struct Recorder {
+    path: PathBuf,
+    format: RecordFormat,
+    fps: u32,
+    downscale: u32,
+    dither: bool,
+    width: u32,
+    height: u32,
+    frames: Vec<Vec<[u8; 3]>>,
+}
+
+impl Recorder {
+    pub fn new(path: &str, fps: u32, downscale: u32, dither: bool) -> io::Result<Self> {
+        let path = PathBuf::from(path);
+        let format = RecordFormat::from_path(&path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("formato de grabación no soportado: {} (usa .gif o .png)", path.display()),
+            )
+        })?;
+
+        Ok(Recorder {
+            path,
+            format,
+            fps: fps.max(1),
+            downscale: downscale.max(1),
+            dither,
+            width: 0,
+            height: 0,
+            frames: Vec::new(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Tamaño de los cuadros guardados, ya reducidos
+    fn frame_size(&self, framebuffer: &Framebuffer) -> (u32, u32) {
+        let width = (framebuffer.width.max(0) as u32 / self.downscale).max(1);
+        let height = (framebuffer.height.max(0) as u32 / self.downscale).max(1);
+        (width, height)
+    }
+
+    // Falla si `frames` cuadros de este framebuffer no entran en el tope de memoria
+    pub fn check_capacity(&self, framebuffer: &Framebuffer, frames: usize) -> io::Result<()> {
+        let (width, height) = self.frame_size(framebuffer);
+        let max_frames = MAX_RECORDING_BYTES / (width as usize * height as usize * 3);
+        if frames <= max_frames {
+            return Ok(());
+        }
+        Err(io::Error::new(
+            io::ErrorKind::OutOfMemory,
+            format!(
+                "caben {max_frames} cuadros de {width}x{height} ({} MiB en memoria); \
+                 acorta --seconds o baja --fps, o reduce la resolución con --downscale",
+                MAX_RECORDING_BYTES >> 20
+            ),
+        ))
+    }
+
+    pub fn capture(&mut self, framebuffer: &Framebuffer) -> io::Result<()> {
+        let source_width = framebuffer.width.max(0) as u32;
+        let (width, height) = self.frame_size(framebuffer);
+
+        if self.frames.is_empty() {
+            self.width = width;
+            self.height = height;
+        } else if (width, height) != (self.width, self.height) {
+            return Ok(());
+        }
+        self.check_capacity(framebuffer, self.frames.len() + 1)?;
+
+        let pixels = framebuffer.color_buffer.get_image_data();
+        let factor = self.downscale;
+        let samples = factor * factor;
+        let mut frame = Vec::with_capacity((width * height) as usize);
+
+        // Promedio por bloques factor x factor
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = [0u32; 3];
+                for dy in 0..factor {
+                    for dx in 0..factor {
+                        let index = ((y * factor + dy) * source_width + x * factor + dx) as usize;
+                        let color = pixels[index];
+                        sum[0] += color.r as u32;
+                        sum[1] += color.g as u32;
+                        sum[2] += color.b as u32;
+                    }
+                }
+                frame.push([
+                    (sum[0] / samples) as u8,
+                    (sum[1] / samples) as u8,
+                    (sum[2] / samples) as u8,
+                ]);
+            }
+        }
+
+        self.frames.push(frame);
+        Ok(())
+    }
+
+    pub fn finish(self) -> io::Result<usize> {
+        if self.frames.is_empty() {
+            return Ok(0);
+        }
+
+        let file = BufWriter::new(File::create(&self.path)?);
+        match self.format {
+            RecordFormat::Gif => self.write_gif(file)?,
+            RecordFormat::Apng => self.write_apng(file)?,
+        }
+        Ok(self.frames.len())
+    }
+
+    fn write_gif(&self, file: BufWriter<File>) -> io::Result<()> {
+        let palette = median_cut_palette(&self.frames, PALETTE_SIZE);
+        let flat_palette: Vec<u8> = palette.iter().flatten().copied().collect();
+        let mut lookup = PaletteLookup::new(&palette);
+
+        let mut encoder = gif::Encoder::new(file, self.width as u16, self.height as u16, &flat_palette)
+            .map_err(io::Error::other)?;
+        encoder.set_repeat(gif::Repeat::Infinite).map_err(io::Error::other)?;
+
+        // Los retardos GIF van en centésimas; se acumula el redondeo para no desfasar
+        let mut elapsed_cs = 0u64;
+        for (i, frame) in self.frames.iter().enumerate() {
+            let indices = if self.dither {
+                dither_frame(frame, self.width as usize, self.height as usize, &palette, &mut lookup)
+            } else {
+                frame.iter().map(|&pixel| lookup.nearest(pixel)).collect()
+            };
+
+            let target_cs = ((i as u64 + 1) * 100 + self.fps as u64 / 2) / self.fps as u64;
+            let delay = (target_cs - elapsed_cs).max(1);
+            elapsed_cs += delay;
+
+            let gif_frame = gif::Frame {
+                width: self.width as u16,
+                height: self.height as u16,
+                delay: delay as u16,
+                buffer: Cow::Owned(indices),
+                ..gif::Frame::default()
+            };
+            encoder.write_frame(&gif_frame).map_err(io::Error::other)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_apng(&self, file: BufWriter<File>) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(file, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(self.frames.len() as u32, 0).map_err(io::Error::other)?;
+        encoder.set_frame_delay(1, self.fps.min(u16::MAX as u32) as u16).map_err(io::Error::other)?;
+
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        for frame in &self.frames {
+            let data: Vec<u8> = frame.iter().flatten().copied().collect();
+            writer.write_image_data(&data).map_err(io::Error::other)?;
+        }
+        writer.finish().map_err(io::Error::other)
+    }
+}
+
+fn median_cut_palette(frames: &[Vec<[u8; 3]>], size: usize) -> Vec<[u8; 3]> {
+    let total: usize = frames.iter().map(|frame| frame.len()).sum();
+    let stride = (total / MAX_PALETTE_SAMPLES).max(1);
+    let mut samples: Vec<[u8; 3]> = frames.iter().flatten().step_by(stride).copied().collect();
+
+    let mut boxes: Vec<(usize, usize)> = vec![(0, samples.len())];
+
+    while boxes.len() < size {
+        // Se divide la caja con mayor rango ponderado por su cantidad de píxeles
+        let mut best: Option<(usize, usize, u64)> = None;
+        for (i, &(start, end)) in boxes.iter().enumerate() {
+            if end - start < 2 {
+                continue;
+            }
+            let (channel, range) = widest_channel(&samples[start..end]);
+            let score = range as u64 * (end - start) as u64;
+            if range > 0 && best.is_none_or(|(_, _, s)| score > s) {
+                best = Some((i, channel, score));
+            }
+        }
+
+        let Some((index, channel, _)) = best else { break };
+        let (start, end) = boxes[index];
+        samples[start..end].sort_unstable_by_key(|pixel| pixel[channel]);
+        let middle = start + (end - start) / 2;
+        boxes[index] = (start, middle);
+        boxes.push((middle, end));
+    }
+
+    let mut palette: Vec<[u8; 3]> = boxes
+        .iter()
+        .filter(|(start, end)| end > start)
+        .map(|&(start, end)| {
+            let mut sum = [0u64; 3];
+            for pixel in &samples[start..end] {
+                for c in 0..3 {
+                    sum[c] += pixel[c] as u64;
+                }
+            }
+            let count = (end - start) as u64;
+            [(sum[0] / count) as u8, (sum[1] / count) as u8, (sum[2] / count) as u8]
+        })
+        .collect();
+
+    palette.resize(size, [0, 0, 0]);
+    palette
+}
+
+fn widest_channel(pixels: &[[u8; 3]]) -> (usize, u8) {
+    let mut min = [u8::MAX; 3];
+    let mut max = [0u8; 3];
+    for pixel in pixels {
+        for c in 0..3 {
+            min[c] = min[c].min(pixel[c]);
+            max[c] = max[c].max(pixel[c]);
+        }
+    }
+
+    (0..3)
+        .map(|c| (c, max[c] - min[c]))
+        .max_by_key(|&(_, range)| range)
+        .unwrap_or((0, 0))
+}
+
+// Búsqueda del color más cercano con caché de 15 bits (5 por canal)
+struct PaletteLookup<'a> {
+    palette: &'a [[u8; 3]],
+    cache: Vec<u16>,
+}
+
+impl<'a> PaletteLookup<'a> {
+    const EMPTY: u16 = u16::MAX;
+
+    fn new(palette: &'a [[u8; 3]]) -> Self {
+        PaletteLookup {
+            palette,
+            cache: vec![Self::EMPTY; 1 << 15],
+        }
+    }
+
+    fn nearest(&mut self, pixel: [u8; 3]) -> u8 {
+        let key = ((pixel[0] as usize >> 3) << 10) | ((pixel[1] as usize >> 3) << 5) | (pixel[2] as usize >> 3);
+        if self.cache[key] != Self::EMPTY {
+            return self.cache[key] as u8;
+        }
+
+        let mut best_index = 0;
+        let mut best_distance = i32::MAX;
+        for (i, color) in self.palette.iter().enumerate() {
+            let dr = color[0] as i32 - pixel[0] as i32;
+            let dg = color[1] as i32 - pixel[1] as i32;
+            let db = color[2] as i32 - pixel[2] as i32;
+            let distance = 2 * dr * dr + 4 * dg * dg + 3 * db * db;
+            if distance < best_distance {
+                best_distance = distance;
+                best_index = i;
+            }
+        }
+
+        self.cache[key] = best_index as u16;
+        best_index as u8
+    }
+}
+
+// Floyd–Steinberg con recorrido serpenteante
+fn dither_frame(
+    frame: &[[u8; 3]],
+    width: usize,
+    height: usize,
+    palette: &[[u8; 3]],
+    lookup: &mut PaletteLookup,
+) -> Vec<u8> {
+    let mut indices = vec![0u8; width * height];
+    let mut current = vec![[0.0f32; 3]; width + 2];
+    let mut next = vec![[0.0f32; 3]; width + 2];
+
+    for y in 0..height {
+        let left_to_right = y % 2 == 0;
+        let direction: isize = if left_to_right { 1 } else { -1 };
+
+        for step in 0..width {
+            let x = if left_to_right { step } else { width - 1 - step };
+            let pixel = frame[y * width + x];
+            let error = current[x + 1];
+
+            let mut adjusted = [0u8; 3];
+            for c in 0..3 {
+                adjusted[c] = (pixel[c] as f32 + error[c]).round().clamp(0.0, 255.0) as u8;
+            }
+
+            let index = lookup.nearest(adjusted);
+            indices[y * width + x] = index;
+
+            let chosen = palette[index as usize];
+            let forward = (x as isize + 1 + direction) as usize;
+            let backward = (x as isize + 1 - direction) as usize;
+            for c in 0..3 {
+                let diff = adjusted[c] as f32 - chosen[c] as f32;
+                current[forward][c] += diff * 7.0 / 16.0;
+                next[backward][c] += diff * 3.0 / 16.0;
+                next[x + 1][c] += diff * 5.0 / 16.0;
+                next[forward][c] += diff * 1.0 / 16.0;
+            }
+        }
+
+        std::mem::swap(&mut current, &mut next);
+        next.iter_mut().for_each(|error| *error = [0.0; 3]);
+    }
+
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capacity_scales_with_downscale() {
+        let framebuffer = Framebuffer::new(1300, 900);
+        let full = Recorder::new("prueba.gif", 10, 1, true).unwrap();
+        let half = Recorder::new("prueba.gif", 10, 2, true).unwrap();
+
+        // 1300x900 en RGB son ~3.5 MB por cuadro: entran unos 300
+        assert!(full.check_capacity(&framebuffer, 300).is_ok());
+        let err = full.check_capacity(&framebuffer, 400).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::OutOfMemory);
+        assert!(err.to_string().contains("1300x900"), "{err}");
+        assert!(half.check_capacity(&framebuffer, 400).is_ok());
+    }
+}
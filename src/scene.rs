@@ -0,0 +1,1060 @@
+use crate::camera::Camera;
+use crate::ephemeris::{Calendar, Planet, DAYS_PER_TIME_UNIT};
+use crate::light::{Attenuation, Light};
+use crate::material::{Material, MaterialRegistry, MtlMaterial, ParamValue};
+use crate::matrix::{quaternion_from_axis_angle, quaternion_look_rotation, Transform};
+use crate::nbody::{Integrator, NBody, Particle};
+use crate::obj_loader::{Obj, ObjMaterial};
+use crate::orbit::{Orbit, OrbitalElements};
+use crate::planet::{PlanetClass, PlanetParams};
+use crate::scene_graph::{Drawable, NodeId, SceneGraph};
+use crate::shaders::{PlanetMaterial, PlanetRingMaterial};
+use crate::texture::{FilterMode, WrapMode};
+use crate::vertex::Vertex;
+use glam::{Quat, Vec3A};
+use raylib::prelude::*;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::f32::consts::TAU;
+use std::fs;
+
+pub const DEFAULT_SCENE_PATH: &str = "assets/scenes/solar_system.toml";
+
+// ====== Formato del archivo (TOML) ======
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneFile {
+    camera: CameraEntry,
+    #[serde(default)]
+    background: BackgroundEntry,
+    meshes: BTreeMap<String, String>,
+    #[serde(default)]
+    lights: Vec<LightEntry>,
+    bodies: Vec<BodyEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraEntry {
+    eye: [f32; 3],
+    target: [f32; 3],
+    #[serde(default = "default_up")]
+    up: [f32; 3],
+    follow: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+struct BackgroundEntry {
+    color: [u8; 3],
+    stars: u32,
+    star_seed: u64,
+}
+
+impl Default for BackgroundEntry {
+    fn default() -> Self {
+        BackgroundEntry { color: [5, 5, 15], stars: 600, star_seed: 42 }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+enum LightEntry {
+    Ambient {
+        color: [f32; 3],
+        intensity: f32,
+    },
+    Point {
+        position: [f32; 3],
+        color: [f32; 3],
+        intensity: f32,
+        range: Option<f32>,
+    },
+    Directional {
+        direction: [f32; 3],
+        color: [f32; 3],
+        intensity: f32,
+    },
+    Spot {
+        position: [f32; 3],
+        direction: [f32; 3],
+        inner_angle: f32,
+        outer_angle: f32,
+        color: [f32; 3],
+        intensity: f32,
+        range: Option<f32>,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BodyEntry {
+    name: String,
+    parent: Option<String>,
+    #[serde(default = "default_mesh")]
+    mesh: String,
+    material: Option<String>,
+    planet: Option<PlanetEntry>,
+    #[serde(default = "default_scale")]
+    scale: ScaleEntry,
+    // Periodo de rotación; negativo para girar en sentido retrógrado, sin él no gira
+    rotation_period: Option<f32>,
+    // Ángulo de rotación en el instante 0, en grados
+    #[serde(default)]
+    initial_rotation: f32,
+    // Rotación sincrónica: la cara +Z mira siempre al padre (reemplaza a `rotation_period`)
+    #[serde(default)]
+    tidally_locked: bool,
+    // Oblicuidad en grados y hacia dónde se inclina el eje (azimut desde +X hacia +Z); `axis`
+    // da el eje directamente
+    #[serde(default)]
+    tilt: f32,
+    #[serde(default)]
+    tilt_direction: f32,
+    axis: Option<[f32; 3]>,
+    // Parámetro gravitacional (G = 1 en unidades de la escena) que usan sus satélites
+    #[serde(default)]
+    mass: f32,
+    orbit: Option<OrbitEntry>,
+    // Planeta real cuya posición se usa en el modo fecha
+    ephemeris: Option<String>,
+    #[serde(default)]
+    path: PathEntry,
+    ring: Option<RingEntry>,
+    // Muestreo de las texturas `map_Kd` del MTL de la malla
+    #[serde(default)]
+    texture: TextureEntry,
+    #[serde(default)]
+    params: BTreeMap<String, ParamEntry>,
+    // Lunas declaradas dentro del cuerpo (`[[bodies.moons]]`); su padre es este cuerpo
+    #[serde(default)]
+    moons: Vec<BodyEntry>,
+}
+
+// Elementos orbitales con los ángulos en grados; sin `period` se usa la tercera ley de Kepler
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OrbitEntry {
+    semi_major_axis: f32,
+    #[serde(default)]
+    eccentricity: f32,
+    #[serde(default)]
+    inclination: f32,
+    #[serde(default)]
+    ascending_node: f32,
+    #[serde(default)]
+    argument_of_periapsis: f32,
+    #[serde(default)]
+    mean_anomaly: f32,
+    period: Option<f32>,
+}
+
+impl OrbitEntry {
+    fn circular(radius: f32) -> Self {
+        OrbitEntry {
+            semi_major_axis: radius,
+            eccentricity: 0.0,
+            inclination: 0.0,
+            ascending_node: 0.0,
+            argument_of_periapsis: 0.0,
+            mean_anomaly: 0.0,
+            period: None,
+        }
+    }
+
+    fn elements(&self) -> OrbitalElements {
+        OrbitalElements {
+            semi_major_axis: self.semi_major_axis,
+            eccentricity: self.eccentricity,
+            inclination: self.inclination.to_radians(),
+            ascending_node: self.ascending_node.to_radians(),
+            argument_of_periapsis: self.argument_of_periapsis.to_radians(),
+            mean_anomaly: self.mean_anomaly.to_radians(),
+        }
+    }
+}
+
+// Cómo se dibuja el recorrido: la elipse de la órbita y una estela de posiciones recientes
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+struct PathEntry {
+    visible: bool,
+    color: [f32; 3],
+    opacity: f32,
+    orbit: bool,
+    // Segundos de estela; 0 la desactiva
+    trail: f32,
+}
+
+impl Default for PathEntry {
+    fn default() -> Self {
+        PathEntry { visible: true, color: [0.45, 0.5, 0.65], opacity: 0.35, orbit: true, trail: 0.0 }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TextureEntry {
+    wrap: Option<String>,
+    filter: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PlanetEntry {
+    class: String,
+    #[serde(default)]
+    seed: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RingEntry {
+    material: Option<String>,
+    #[serde(default = "default_mesh")]
+    mesh: String,
+    #[serde(default)]
+    params: BTreeMap<String, ParamEntry>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(untagged)]
+enum ParamEntry {
+    Float(f32),
+    Color([f32; 3]),
+}
+
+// Un número escala por igual; `[x, y, z]` achata o estira (planetas oblatos, piezas de la nave)
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(untagged)]
+enum ScaleEntry {
+    Uniform(f32),
+    Axes([f32; 3]),
+}
+
+impl ScaleEntry {
+    fn vector(self) -> Vec3A {
+        match self {
+            ScaleEntry::Uniform(scale) => Vec3A::splat(scale),
+            ScaleEntry::Axes(axes) => vec3a(axes),
+        }
+    }
+}
+
+fn default_up() -> [f32; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+fn default_mesh() -> String {
+    "sphere".to_string()
+}
+
+fn default_scale() -> ScaleEntry {
+    ScaleEntry::Uniform(1.0)
+}
+
+fn vec3a(v: [f32; 3]) -> Vec3A {
+    Vec3A::from(v)
+}
+
+// ====== Escena cargada ======
+
+// Submalla de un modelo con su entrada del MTL, si la tiene
+pub struct MeshPart {
+    pub vertices: Vec<Vertex>,
+    pub material: Option<ObjMaterial>,
+}
+
+pub struct Mesh {
+    pub parts: Vec<MeshPart>,
+    // Radio de la esfera centrada en el origen que envuelve todas las partes
+    pub radius: f32,
+}
+
+impl Mesh {
+    fn load(path: &str) -> Result<Self, String> {
+        let obj = Obj::load(path).map_err(|err| format!("No se pudo cargar {path}: {err}"))?;
+        let parts = obj
+            .submeshes
+            .iter()
+            .map(|submesh| MeshPart {
+                vertices: obj.submesh_vertex_array(submesh),
+                material: obj.material(submesh).cloned(),
+            })
+            .collect::<Vec<MeshPart>>();
+        let radius = parts
+            .iter()
+            .flat_map(|part| &part.vertices)
+            .map(|vertex| vertex.position.length())
+            .fold(0.0, f32::max);
+        Ok(Mesh { parts, radius })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PathStyle {
+    pub visible: bool,
+    pub color: Vec3A,
+    pub opacity: f32,
+    pub orbit: bool,
+    pub trail: f32,
+}
+
+// Posiciones absolutas recientes con su instante, de la más vieja a la más nueva
+#[derive(Debug, Clone, Default)]
+pub struct Trail {
+    pub points: VecDeque<(f32, Vec3A)>,
+}
+
+impl Trail {
+    fn push(&mut self, time: f32, position: Vec3A, length: f32) {
+        // El tiempo solo retrocede si se reinicia la escena; entonces la estela empieza de nuevo
+        match self.points.back() {
+            Some(&(last, _)) if time == last => return,
+            Some(&(last, _)) if time < last => self.points.clear(),
+            _ => {}
+        }
+        self.points.push_back((time, position));
+        while self.points.front().is_some_and(|&(start, _)| time - start > length) {
+            self.points.pop_front();
+        }
+    }
+}
+
+// Cada cuerpo ocupa tres nodos: el pivote sigue la órbita, el ecuador inclina el eje (y es
+// el padre de sus lunas, que orbitan en su plano ecuatorial) y el cuerpo gira y escala sin
+// arrastrar a sus hijos
+pub struct Body {
+    pub name: String,
+    // Índice del cuerpo padre en `Scene::bodies`
+    pub parent: Option<usize>,
+    pub pivot: NodeId,
+    pub equator: NodeId,
+    pub node: NodeId,
+    // Lleva el eje Y local al eje de giro
+    pub tilt: Quat,
+    // Velocidad angular (radianes por unidad de tiempo) y ángulo inicial
+    pub spin: f32,
+    pub initial_rotation: f32,
+    // Gira con su órbita para mostrarle al padre siempre la misma cara
+    pub tidally_locked: bool,
+    pub mass: f32,
+    pub orbit: Option<Orbit>,
+    // Planeta real y unidades de la escena por UA (semieje de la escena sobre el real)
+    pub ephemeris: Option<(Planet, f32)>,
+    pub path: PathStyle,
+    pub trail: Trail,
+}
+
+pub struct Scene {
+    pub camera: Camera,
+    pub follow: Option<NodeId>,
+    pub background: Color,
+    pub stars: u32,
+    pub star_seed: u64,
+    pub meshes: HashMap<String, Mesh>,
+    pub graph: SceneGraph,
+    pub bodies: Vec<Body>,
+    pub lights: Vec<Light>,
+    pub materials: HashMap<String, Box<dyn Material>>,
+    // Con simulación activa las posiciones salen del integrador y no de las órbitas
+    pub simulation: Option<NBody>,
+    pub integrator: Integrator,
+    // En el modo fecha los cuerpos con `ephemeris` siguen sus posiciones reales
+    pub calendar: Option<Calendar>,
+    // Interruptor global de órbitas y estelas
+    pub show_paths: bool,
+}
+
+impl Scene {
+    // Carga y valida la escena; `extra_planets` agrega mundos generados al final
+    pub fn load(path: &str, registry: &MaterialRegistry, extra_planets: &[(PlanetClass, u64)]) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("No se pudo leer la escena {path}: {err}"))?;
+        let mut file: SceneFile = toml::from_str(&text).map_err(|err| format!("{path}: {err}"))?;
+
+        let last_radius = file
+            .bodies
+            .iter()
+            .filter(|body| body.parent.is_none())
+            .filter_map(|body| body.orbit.map(|orbit| orbit.elements().apoapsis()))
+            .fold(0.0, f32::max);
+        for (index, &(class, seed)) in extra_planets.iter().enumerate() {
+            let radius = last_radius + 4.0 * (index + 1) as f32;
+            file.bodies.push(BodyEntry {
+                name: format!("generated/{index}"),
+                parent: None,
+                mesh: default_mesh(),
+                material: None,
+                planet: Some(PlanetEntry { class: class.name().to_string(), seed }),
+                scale: ScaleEntry::Uniform(PlanetParams::generate(seed, class).radius),
+                rotation_period: Some(TAU / 3.0),
+                initial_rotation: 0.0,
+                tidally_locked: false,
+                tilt: 0.0,
+                tilt_direction: 0.0,
+                axis: None,
+                mass: 0.0,
+                orbit: Some(OrbitEntry::circular(radius)),
+                ephemeris: None,
+                path: PathEntry::default(),
+                ring: None,
+                texture: TextureEntry::default(),
+                params: BTreeMap::new(),
+                moons: Vec::new(),
+            });
+        }
+
+        Self::build(file, registry).map_err(|err| format!("{path}: {err}"))
+    }
+
+    fn build(file: SceneFile, registry: &MaterialRegistry) -> Result<Self, String> {
+        let mut meshes = HashMap::new();
+        for (name, path) in &file.meshes {
+            let mesh = Mesh::load(path).map_err(|err| format!("meshes.{name}: {err}"))?;
+            meshes.insert(name.clone(), mesh);
+        }
+
+        let lights = file.lights.iter().enumerate().map(|(index, light)| build_light(index, light)).collect::<Result<_, _>>()?;
+
+        let entries = flatten_moons(file.bodies, "bodies")?;
+        let mut materials: HashMap<String, Box<dyn Material>> = HashMap::new();
+        let mut bodies: Vec<Body> = Vec::with_capacity(entries.len());
+        let mut graph = SceneGraph::new();
+
+        for (source, entry) in &entries {
+            let fail = |message: String| format!("{source} \"{}\": {message}", entry.name);
+
+            if entry.name.is_empty() {
+                return Err(format!("{source}: falta el nombre"));
+            }
+            if bodies.iter().any(|body| body.name == entry.name) {
+                return Err(fail("nombre repetido".to_string()));
+            }
+            let scale = entry.scale.vector();
+            if scale.x <= 0.0 || scale.y <= 0.0 || scale.z <= 0.0 {
+                return Err(fail(format!("la escala debe ser positiva ({}, {}, {})", scale.x, scale.y, scale.z)));
+            }
+            if !(0.0..=1.0).contains(&entry.path.opacity) || entry.path.trail < 0.0 {
+                return Err(fail("path: `opacity` debe estar en [0, 1] y `trail` no puede ser negativo".to_string()));
+            }
+            let tilt = body_tilt(entry).map_err(fail)?;
+            let spin = match entry.rotation_period {
+                Some(0.0) => return Err(fail("`rotation_period` no puede ser 0".to_string())),
+                Some(period) => TAU / period,
+                None => 0.0,
+            };
+            if entry.mass < 0.0 {
+                return Err(fail("la masa no puede ser negativa".to_string()));
+            }
+            let mesh = meshes.get(&entry.mesh).ok_or_else(|| fail(format!("malla desconocida \"{}\"", entry.mesh)))?;
+            let parent = match &entry.parent {
+                Some(parent) => Some(
+                    bodies
+                        .iter()
+                        .position(|body| &body.name == parent)
+                        .ok_or_else(|| fail(format!("padre desconocido \"{parent}\" (debe declararse antes)")))?,
+                ),
+                None => None,
+            };
+            if entry.tidally_locked && (parent.is_none() || entry.rotation_period.is_some()) {
+                return Err(fail("`tidally_locked` necesita un padre y no se combina con `rotation_period`".to_string()));
+            }
+
+            // Se orbita al padre o, sin padre, al primer cuerpo raíz que no orbita (la estrella)
+            let orbit = match &entry.orbit {
+                Some(orbit) => {
+                    let central = match parent {
+                        Some(parent) => bodies.get(parent),
+                        None => bodies.iter().find(|body| body.orbit.is_none() && body.parent.is_none()),
+                    };
+                    Some(build_orbit(orbit, central.map_or(0.0, |body| body.mass)).map_err(fail)?)
+                }
+                None => None,
+            };
+
+            let ephemeris = match &entry.ephemeris {
+                Some(name) => {
+                    let planet = Planet::from_name(name).ok_or_else(|| {
+                        let known: Vec<&str> = Planet::ALL.iter().map(|planet| planet.name()).collect();
+                        fail(format!("ephemeris: planeta desconocido \"{name}\" (disponibles: {})", known.join(", ")))
+                    })?;
+                    let (Some(orbit), None) = (&entry.orbit, parent) else {
+                        return Err(fail("ephemeris: el cuerpo debe orbitar al Sol con su propia `orbit`".to_string()));
+                    };
+                    Some((planet, orbit.semi_major_axis / planet.semi_major_axis()))
+                }
+                None => None,
+            };
+
+            let (mut base, ring_params) = match (&entry.material, &entry.planet) {
+                (Some(name), None) => {
+                    let material = registry.create(name).ok_or_else(|| fail(format!("material desconocido \"{name}\"")))?;
+                    (material, None)
+                }
+                (None, Some(planet)) => {
+                    let class = PlanetClass::from_name(&planet.class)
+                        .ok_or_else(|| fail(format!("clase de planeta desconocida \"{}\"", planet.class)))?;
+                    let params = PlanetParams::generate(planet.seed, class);
+                    let ring = params.ring.clone();
+                    (Box::new(PlanetMaterial::new(params)) as Box<dyn Material>, ring)
+                }
+                _ => return Err(fail("se necesita exactamente uno de `material` o `planet`".to_string())),
+            };
+            // Las claves `mtl_*` pertenecen a las entradas del MTL y se validan al envolverlas
+            let has_mtl = mesh.parts.iter().any(|part| part.material.is_some());
+            let base_params: BTreeMap<String, ParamEntry> = entry
+                .params
+                .iter()
+                .filter(|(name, _)| !(has_mtl && name.starts_with("mtl_")))
+                .map(|(name, value)| (name.clone(), *value))
+                .collect();
+            apply_params(base.as_mut(), &base_params).map_err(fail)?;
+
+            let wrap = match &entry.texture.wrap {
+                Some(name) => Some(WrapMode::from_name(name).ok_or_else(|| {
+                    fail(format!("texture.wrap: modo desconocido \"{name}\" (repeat, clamp o mirror)"))
+                })?),
+                None => None,
+            };
+            let filter = match &entry.texture.filter {
+                Some(name) => FilterMode::from_name(name).ok_or_else(|| {
+                    fail(format!("texture.filter: filtro desconocido \"{name}\" (nearest, bilinear o trilinear)"))
+                })?,
+                None => FilterMode::default(),
+            };
+
+            // Cada entrada del MTL envuelve al material del cuerpo; las translúcidas van al final
+            let mut parts: Vec<(f32, Drawable)> = Vec::with_capacity(mesh.parts.len());
+            for (part_index, part) in mesh.parts.iter().enumerate() {
+                match &part.material {
+                    Some(mtl) => {
+                        let name = format!("{}/{}", entry.name, mtl.name);
+                        if !materials.contains_key(&name) {
+                            let mut material = MtlMaterial::new(mtl, base_for_mtl(entry, registry)?)
+                                .with_texture_options(wrap, filter);
+                            apply_params(&mut material, &entry.params).map_err(fail)?;
+                            materials.insert(name.clone(), Box::new(material));
+                        }
+                        parts.push((mtl.dissolve, drawable(&entry.mesh, part_index, name, false)));
+                    }
+                    None => parts.push((1.0, drawable(&entry.mesh, part_index, entry.name.clone(), false))),
+                }
+            }
+            parts.sort_by(|a, b| b.0.total_cmp(&a.0));
+            materials.insert(entry.name.clone(), base);
+
+            let ring = match (&entry.ring, ring_params) {
+                (Some(ring), generated) => {
+                    if !meshes.contains_key(&ring.mesh) {
+                        return Err(fail(format!("ring: malla desconocida \"{}\"", ring.mesh)));
+                    }
+                    let mut material = match (&ring.material, generated) {
+                        (Some(name), _) => registry.create(name).ok_or_else(|| fail(format!("ring: material desconocido \"{name}\"")))?,
+                        (None, Some(generated)) => Box::new(PlanetRingMaterial::new(generated)),
+                        (None, None) => return Err(fail("ring: falta `material`".to_string())),
+                    };
+                    apply_params(material.as_mut(), &ring.params).map_err(|err| fail(format!("ring: {err}")))?;
+                    Some((ring.mesh.clone(), material))
+                }
+                (None, Some(generated)) => Some((default_mesh(), Box::new(PlanetRingMaterial::new(generated)) as Box<dyn Material>)),
+                (None, None) => None,
+            };
+
+            let pivot = graph.add_node(parent.map(|parent| bodies[parent].equator), Transform::default());
+            let equator = graph.add_node(Some(pivot), Transform::default().with_rotation(tilt));
+            let node = graph.add_node(Some(equator), Transform::default().with_non_uniform_scale(scale));
+            graph.node_mut(node).drawables = parts.into_iter().map(|(_, part)| part).collect();
+
+            // El anillo cuelga del cuerpo: hereda su escala, su inclinación y su giro
+            if let Some((ring_mesh, material)) = ring {
+                let name = format!("{}/ring", entry.name);
+                materials.insert(name.clone(), material);
+                let ring_node = graph.add_node(Some(node), Transform::default());
+                graph.node_mut(ring_node).drawables = (0..meshes[&ring_mesh].parts.len())
+                    .map(|part| drawable(&ring_mesh, part, name.clone(), true))
+                    .collect();
+            }
+
+            bodies.push(Body {
+                name: entry.name.clone(),
+                parent,
+                pivot,
+                equator,
+                node,
+                tilt,
+                spin,
+                initial_rotation: entry.initial_rotation.to_radians(),
+                tidally_locked: entry.tidally_locked,
+                mass: entry.mass,
+                orbit,
+                ephemeris,
+                path: PathStyle {
+                    visible: entry.path.visible,
+                    color: vec3a(entry.path.color),
+                    opacity: entry.path.opacity,
+                    orbit: entry.path.orbit,
+                    trail: entry.path.trail,
+                },
+                trail: Trail::default(),
+            });
+        }
+
+        let camera = &file.camera;
+        if camera.eye == camera.target {
+            return Err("camera: `eye` y `target` no pueden coincidir".to_string());
+        }
+        let follow = match &camera.follow {
+            Some(name) => Some(
+                bodies
+                    .iter()
+                    .find(|body| &body.name == name)
+                    .map(|body| body.pivot)
+                    .ok_or_else(|| format!("camera.follow: cuerpo desconocido \"{name}\""))?,
+            ),
+            None => None,
+        };
+
+        Ok(Scene {
+            camera: Camera::new(vec3a(camera.eye), vec3a(camera.target), vec3a(camera.up)),
+            follow,
+            background: Color::new(file.background.color[0], file.background.color[1], file.background.color[2], 255),
+            stars: file.background.stars,
+            star_seed: file.background.star_seed,
+            meshes,
+            graph,
+            bodies,
+            lights,
+            materials,
+            simulation: None,
+            integrator: Integrator::default(),
+            calendar: None,
+            show_paths: true,
+        })
+    }
+
+    // Posición y velocidad de un cuerpo respecto de su padre, en ejes de mundo. Las órbitas de
+    // las lunas están en el plano ecuatorial del padre. En el modo fecha los cuerpos con
+    // efemérides van a su posición real, escalada a la escena.
+    fn relative_state(&self, body: &Body, time: f32, date: Option<f64>) -> (Vec3A, Vec3A) {
+        if let (Some((planet, scale)), Some(date)) = (body.ephemeris, date) {
+            // Velocidad por diferencia central de medio día a cada lado
+            let ahead = planet.heliocentric_position(date + 0.5);
+            let behind = planet.heliocentric_position(date - 0.5);
+            let velocity = (ahead - behind) * (scale * DAYS_PER_TIME_UNIT as f32);
+            return (planet.heliocentric_position(date) * scale, velocity);
+        }
+        let tilt = body.parent.map_or(Quat::IDENTITY, |parent| self.bodies[parent].tilt);
+        match body.orbit {
+            Some(orbit) => (tilt * orbit.position(time), tilt * orbit.velocity(time)),
+            None => (Vec3A::ZERO, Vec3A::ZERO),
+        }
+    }
+
+    // Posición y velocidad absolutas de cada cuerpo según sus órbitas (los padres van antes)
+    fn scripted_state(&self, time: f32) -> Vec<(Vec3A, Vec3A)> {
+        let date = self.date(time);
+        let mut state: Vec<(Vec3A, Vec3A)> = Vec::with_capacity(self.bodies.len());
+        for body in &self.bodies {
+            let (position, velocity) = self.relative_state(body, time, date);
+            state.push(match body.parent {
+                Some(parent) => (state[parent].0 + position, state[parent].1 + velocity),
+                None => (position, velocity),
+            });
+        }
+        state
+    }
+
+    // Solo se simulan los cuerpos sin padre: con las masas comprimidas de la escena las lunas
+    // quedarían fuera de la esfera de Hill de su planeta, así que siguen su órbita alrededor
+    // de la posición simulada del padre
+    fn simulated_positions(&self, simulation: &NBody, time: f32) -> Vec<Vec3A> {
+        let mut roots = simulation.particles.iter();
+        let mut positions: Vec<Vec3A> = Vec::with_capacity(self.bodies.len());
+        for body in &self.bodies {
+            positions.push(match body.parent {
+                Some(parent) => positions[parent] + self.relative_state(body, time, None).0,
+                None => roots.next().map_or(Vec3A::ZERO, |particle| particle.position),
+            });
+        }
+        positions
+    }
+
+    // Alterna entre órbitas keplerianas y simulación; la simulación parte del estado actual.
+    // Las masas de la escena no corresponden a las reales, así que sale del modo fecha.
+    pub fn set_simulated(&mut self, simulated: bool, time: f32) {
+        if !simulated {
+            self.simulation = None;
+            return;
+        }
+        if self.calendar.is_some() {
+            self.set_date(None, time);
+        }
+
+        let mut particles: Vec<Particle> = self
+            .scripted_state(time)
+            .into_iter()
+            .zip(&self.bodies)
+            .filter(|(_, body)| body.parent.is_none())
+            .map(|((position, velocity), body)| Particle { mass: body.mass, position, velocity })
+            .collect();
+
+        // Sin esta corrección el sistema entero se desplaza con el momento inicial de los planetas
+        let total_mass: f32 = particles.iter().map(|p| p.mass).sum();
+        if total_mass > 0.0 {
+            let drift = particles.iter().fold(Vec3A::ZERO, |sum, p| sum + p.velocity * p.mass) / total_mass;
+            for particle in &mut particles {
+                particle.velocity -= drift;
+            }
+        }
+
+        self.simulation = Some(NBody::new(particles, self.integrator, time));
+    }
+
+    // Entra al modo fecha en el día juliano `date` (o sale con None). Los cuerpos saltan de
+    // posición, así que las estelas empiezan de nuevo.
+    pub fn set_date(&mut self, date: Option<f64>, time: f32) {
+        self.calendar = date.map(|epoch| Calendar { epoch, start: time });
+        if self.calendar.is_some() {
+            self.simulation = None;
+        }
+        for body in &mut self.bodies {
+            body.trail.points.clear();
+        }
+    }
+
+    pub fn date(&self, time: f32) -> Option<f64> {
+        self.calendar.map(|calendar| calendar.julian_day(time))
+    }
+
+    pub fn cycle_integrator(&mut self) {
+        self.integrator = self.integrator.next();
+        if let Some(simulation) = &mut self.simulation {
+            simulation.integrator = self.integrator;
+        }
+    }
+
+    // Mueve cada pivote a su posición, gira los cuerpos y propaga las matrices de mundo
+    pub fn update(&mut self, time: f32) {
+        if let Some(simulation) = &mut self.simulation {
+            simulation.advance_to(time);
+        }
+        let positions: Vec<Vec3A> = match &self.simulation {
+            Some(simulation) => self.simulated_positions(simulation, time),
+            None => self.scripted_state(time).into_iter().map(|(position, _)| position).collect(),
+        };
+
+        let tilts: Vec<Quat> = self.bodies.iter().map(|body| body.tilt).collect();
+        for (body, &position) in self.bodies.iter_mut().zip(&positions) {
+            // El pivote de una luna vive en el marco ecuatorial (inclinado) de su padre
+            let translation = match body.parent {
+                Some(parent) => tilts[parent].inverse() * (position - positions[parent]),
+                None => position,
+            };
+            self.graph.node_mut(body.pivot).local.translation = translation;
+            if body.path.trail > 0.0 {
+                body.trail.push(time, position, body.path.trail);
+            }
+            let axis = Vec3A::Y;
+            self.graph.node_mut(body.node).local.rotation = if body.tidally_locked {
+                // Anclada al padre, gira sobre su eje lo justo para que +Z apunte hacia él
+                let toward = body.tilt.inverse() * -translation;
+                quaternion_look_rotation(Vec3A::new(toward.x, 0.0, toward.z), axis)
+                    * quaternion_from_axis_angle(axis, body.initial_rotation)
+            } else {
+                quaternion_from_axis_angle(axis, body.initial_rotation + time * body.spin)
+            };
+        }
+        self.graph.update_world();
+
+        if let Some(node) = self.follow {
+            self.camera.set_target(self.graph.node(node).world_position());
+        }
+    }
+
+    // Sigue a otro cuerpo (o a ninguno); la cámara gira hacia él desde donde está
+    pub fn follow_body(&mut self, body: Option<usize>) {
+        self.follow = body.map(|body| self.bodies[body].pivot);
+        if self.follow.is_some() {
+            self.camera.turn();
+        }
+    }
+
+    // Cuerpo más cercano que corta el rayo, con la esfera que envuelve su malla escalada
+    pub fn pick(&self, origin: Vec3A, direction: Vec3A) -> Option<usize> {
+        let direction = direction.normalize();
+        self.bodies
+            .iter()
+            .enumerate()
+            .filter_map(|(index, body)| {
+                let node = self.graph.node(body.node);
+                let mesh_radius = node.drawables.first().map_or(1.0, |drawable| self.meshes[&drawable.mesh].radius);
+                let radius = mesh_radius * node.local.scale.x.max(node.local.scale.y).max(node.local.scale.z);
+                let center = node.world_position();
+                let along = (center - origin).dot(direction);
+                let miss = (center - (origin + direction * along)).length();
+                (along > 0.0 && miss <= radius).then_some((index, along))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index)
+    }
+
+    // Elipse de la órbita en coordenadas de mundo, centrada en la posición actual del padre y
+    // en su plano ecuatorial. En el modo fecha se dibuja la órbita real del día.
+    pub fn orbit_path(&self, body: &Body, segments: usize, time: f32) -> Option<Vec<Vec3A>> {
+        if let (Some((planet, scale)), Some(date)) = (body.ephemeris, self.date(time)) {
+            let mut elements = planet.elements(date);
+            elements.semi_major_axis *= scale;
+            return Some(elements.path(segments));
+        }
+        let orbit = body.orbit?;
+        let (center, tilt) = match body.parent {
+            Some(parent) => {
+                let parent = &self.bodies[parent];
+                (self.graph.node(parent.pivot).world_position(), parent.tilt)
+            }
+            None => (Vec3A::ZERO, Quat::IDENTITY),
+        };
+        Some(orbit.path(segments).into_iter().map(|point| center + tilt * point).collect())
+    }
+
+    pub fn material(&self, name: &str) -> &dyn Material {
+        self.materials
+            .get(name)
+            .map(|material| material.as_ref())
+            .unwrap_or_else(|| panic!("Material no registrado: {name}"))
+    }
+}
+
+// Las lunas se aplanan detrás de su planeta con `parent` apuntándolo, así el resto de la carga
+// ve una sola lista en la que los padres van antes. Cada entrada conserva su ruta en el archivo
+// (`bodies[3].moons[1]`) para que los errores señalen la entrada correcta.
+fn flatten_moons(entries: Vec<BodyEntry>, path: &str) -> Result<Vec<(String, BodyEntry)>, String> {
+    let mut flat = Vec::with_capacity(entries.len());
+    for (index, mut entry) in entries.into_iter().enumerate() {
+        let source = format!("{path}[{index}]");
+        let mut moons = std::mem::take(&mut entry.moons);
+        for (moon_index, moon) in moons.iter_mut().enumerate() {
+            if moon.parent.is_some() {
+                return Err(format!("{source}.moons[{moon_index}] \"{}\": una luna no puede declarar `parent`", moon.name));
+            }
+            moon.parent = Some(entry.name.clone());
+        }
+        let moons = flatten_moons(moons, &format!("{source}.moons"))?;
+        flat.push((source, entry));
+        flat.extend(moons);
+    }
+    Ok(flat)
+}
+
+// Rotación que lleva +Y al eje de giro: se inclina `tilt` grados hacia `tilt_direction`
+fn body_tilt(entry: &BodyEntry) -> Result<Quat, String> {
+    let (tilt, direction) = match entry.axis {
+        Some(_) if entry.tilt != 0.0 || entry.tilt_direction != 0.0 => {
+            return Err("`axis` no se combina con `tilt` ni `tilt_direction`".to_string());
+        }
+        Some(axis) => {
+            let axis = vec3a(axis);
+            let length = axis.length();
+            if length == 0.0 {
+                return Err("`axis` no puede ser nulo".to_string());
+            }
+            ((axis.y / length).clamp(-1.0, 1.0).acos(), axis.z.atan2(axis.x))
+        }
+        None => (entry.tilt.to_radians(), entry.tilt_direction.to_radians()),
+    };
+
+    // Girar +Y alrededor de (sin d, 0, -cos d) lo inclina hacia (cos d, 0, sin d)
+    let hinge = Vec3A::new(direction.sin(), 0.0, -direction.cos());
+    Ok(quaternion_from_axis_angle(hinge, tilt))
+}
+
+fn build_orbit(entry: &OrbitEntry, central_mass: f32) -> Result<Orbit, String> {
+    if entry.semi_major_axis <= 0.0 {
+        return Err(format!("orbit: el semieje mayor debe ser positivo ({})", entry.semi_major_axis));
+    }
+    if !(0.0..1.0).contains(&entry.eccentricity) {
+        return Err(format!("orbit: la excentricidad debe estar en [0, 1) ({})", entry.eccentricity));
+    }
+    match entry.period {
+        Some(period) if period <= 0.0 => Err(format!("orbit: el periodo debe ser positivo ({period})")),
+        Some(period) => Ok(Orbit::with_period(entry.elements(), period)),
+        None if central_mass > 0.0 => Ok(Orbit::new(entry.elements(), central_mass)),
+        None => Err("orbit: se necesita `period` o un cuerpo central con `mass`".to_string()),
+    }
+}
+
+fn drawable(mesh: &str, part: usize, material: String, is_ring: bool) -> Drawable {
+    Drawable { mesh: mesh.to_string(), part, material, is_ring }
+}
+
+// Los materiales MTL necesitan su propia instancia del material procedural base
+fn base_for_mtl(entry: &BodyEntry, registry: &MaterialRegistry) -> Result<Option<Box<dyn Material>>, String> {
+    Ok(match (&entry.material, &entry.planet) {
+        (Some(name), _) => registry.create(name),
+        (None, Some(planet)) => PlanetClass::from_name(&planet.class)
+            .map(|class| Box::new(PlanetMaterial::new(PlanetParams::generate(planet.seed, class))) as Box<dyn Material>),
+        (None, None) => None,
+    })
+}
+
+// Solo se aceptan parámetros que el material ya expone, con el mismo tipo
+fn apply_params(material: &mut dyn Material, params: &BTreeMap<String, ParamEntry>) -> Result<(), String> {
+    for (name, value) in params {
+        let value = match (material.params().get(name), *value) {
+            (Some(ParamValue::Float(_)), ParamEntry::Float(value)) => ParamValue::Float(value),
+            (Some(ParamValue::Color(_)), ParamEntry::Color(value)) => ParamValue::Color(vec3a(value)),
+            (Some(_), _) => return Err(format!("params.{name}: tipo incorrecto para el material \"{}\"", material.name())),
+            (None, _) => {
+                let mut available: Vec<&str> = material.params().names().collect();
+                available.sort_unstable();
+                return Err(format!(
+                    "params.{name}: el material \"{}\" no tiene ese parámetro (disponibles: {})",
+                    material.name(),
+                    available.join(", ")
+                ));
+            }
+        };
+        material.params_mut().set(name, value);
+    }
+    Ok(())
+}
+
+fn build_light(index: usize, light: &LightEntry) -> Result<Light, String> {
+    let attenuate = |light: Light, range: Option<f32>| match range {
+        Some(range) if range <= 0.0 => Err(format!("lights[{index}]: `range` debe ser positivo")),
+        Some(range) => Ok(light.with_attenuation(Attenuation::with_range(range))),
+        None => Ok(light),
+    };
+
+    match *light {
+        LightEntry::Ambient { color, intensity } => Ok(Light::ambient(vec3a(color), intensity)),
+        LightEntry::Point { position, color, intensity, range } => {
+            attenuate(Light::point(vec3a(position), vec3a(color), intensity), range)
+        }
+        LightEntry::Directional { direction, color, intensity } => {
+            if direction == [0.0; 3] {
+                return Err(format!("lights[{index}]: la dirección no puede ser nula"));
+            }
+            Ok(Light::directional(vec3a(direction), vec3a(color), intensity))
+        }
+        LightEntry::Spot { position, direction, inner_angle, outer_angle, color, intensity, range } => {
+            if !(0.0..=180.0).contains(&inner_angle) || !(0.0..=180.0).contains(&outer_angle) {
+                return Err(format!("lights[{index}]: los ángulos del foco van en grados, entre 0 y 180"));
+            }
+            if inner_angle > outer_angle {
+                return Err(format!("lights[{index}]: `inner_angle` no puede superar a `outer_angle`"));
+            }
+            // Los ángulos se escriben en grados, como los de las órbitas y la inclinación
+            let (inner, outer) = (inner_angle.to_radians(), outer_angle.to_radians());
+            let spot = Light::spot(vec3a(position), vec3a(direction), inner, outer, vec3a(color), intensity);
+            attenuate(spot, range)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn orbit(text: &str) -> OrbitEntry {
+        toml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn orbit_eccentricity_must_be_in_range() {
+        for eccentricity in ["-0.1", "1.0", "1.5"] {
+            let entry = orbit(&format!("semi_major_axis = 2.0\neccentricity = {eccentricity}\nperiod = 3.0"));
+            assert!(build_orbit(&entry, 0.0).is_err(), "e = {eccentricity}");
+        }
+        assert!(build_orbit(&orbit("semi_major_axis = 2.0\neccentricity = 0.99\nperiod = 3.0"), 0.0).is_ok());
+    }
+
+    // Foco en el origen apuntando a +Z con cono interior de 20° y exterior de 40°
+    #[test]
+    fn spot_angles_are_read_in_degrees() {
+        let entry: LightEntry = toml::from_str(
+            "kind = \"spot\"\nposition = [0.0, 0.0, 0.0]\ndirection = [0.0, 0.0, 1.0]\n\
+             inner_angle = 20.0\nouter_angle = 40.0\ncolor = [1.0, 1.0, 1.0]\nintensity = 1.0",
+        )
+        .unwrap();
+        let spot = build_light(0, &entry).unwrap();
+        let at = |degrees: f32| {
+            let (sin, cos) = degrees.to_radians().sin_cos();
+            spot.incident(Vec3A::new(sin, 0.0, cos) * 5.0).map(|(_, radiance)| radiance.x)
+        };
+        assert_eq!(at(10.0), Some(1.0));
+        assert!(at(30.0).is_some_and(|radiance| radiance > 0.0 && radiance < 1.0));
+        assert_eq!(at(50.0), None);
+
+        let wide: LightEntry = toml::from_str(
+            "kind = \"spot\"\nposition = [0.0, 0.0, 0.0]\ndirection = [0.0, 0.0, 1.0]\n\
+             inner_angle = 20.0\nouter_angle = 200.0\ncolor = [1.0, 1.0, 1.0]\nintensity = 1.0",
+        )
+        .unwrap();
+        assert!(build_light(0, &wide).is_err());
+    }
+
+    // El Sol en el origen y un planeta a x = 4 (anomalía media 0 en una órbita circular)
+    fn two_body_scene() -> Scene {
+        let path = std::env::temp_dir().join(format!("lab4_pick_{}.toml", std::process::id()));
+        fs::write(
+            &path,
+            r#"
+[camera]
+eye = [0.0, 0.0, 10.0]
+target = [0.0, 0.0, 0.0]
+
+[meshes]
+sphere = "assets/models/sphere.obj"
+
+[[bodies]]
+name = "sun"
+material = "sun"
+mass = 16.0
+
+[[bodies]]
+name = "planet"
+material = "sun"
+scale = 0.5
+orbit = { semi_major_axis = 4.0 }
+"#,
+        )
+        .unwrap();
+        let scene = Scene::load(path.to_str().unwrap(), &MaterialRegistry::with_builtin(), &[]);
+        fs::remove_file(&path).unwrap();
+        let mut scene = scene.unwrap();
+        scene.update(0.0);
+        scene
+    }
+
+    #[test]
+    fn pick_returns_the_nearest_body_hit() {
+        let scene = two_body_scene();
+        let eye = scene.camera.eye;
+        let planet = scene.graph.node(scene.bodies[1].node).world_position();
+
+        assert_eq!(scene.pick(eye, -eye), Some(0));
+        assert_eq!(scene.pick(eye, planet - eye), Some(1));
+        // Roza el borde del planeta pero no el del Sol
+        let radius = scene.meshes["sphere"].radius * 0.5;
+        assert_eq!(scene.pick(eye, planet + Vec3A::new(0.0, radius * 0.9, 0.0) - eye), Some(1));
+        assert_eq!(scene.pick(eye, planet + Vec3A::new(0.0, radius * 1.1, 0.0) - eye), None);
+        // Solo hacia adelante
+        assert_eq!(scene.pick(eye, eye), None);
+    }
+
+    #[test]
+    fn orbit_period_comes_from_the_central_mass() {
+        let entry = orbit("semi_major_axis = 4.0");
+        let from_mass = build_orbit(&entry, 16.0).unwrap();
+        assert!((from_mass.period - std::f32::consts::TAU * 2.0).abs() < 1e-4);
+        assert!(build_orbit(&entry, 0.0).is_err());
+
+        // `period` manda sobre la masa
+        let fixed = build_orbit(&orbit("semi_major_axis = 4.0\nperiod = 3.0"), 16.0).unwrap();
+        assert_eq!(fixed.period, 3.0);
+        assert!(build_orbit(&orbit("semi_major_axis = 4.0\nperiod = 0.0"), 16.0).is_err());
+        assert!(build_orbit(&orbit("semi_major_axis = 0.0\nperiod = 3.0"), 16.0).is_err());
+    }
+}
@@ -0,0 +1,124 @@
+use crate::matrix::Transform;
+use glam::{Mat4, Vec3A};
+
+pub type NodeId = usize;
+
+// Una submalla que se dibuja con la matriz de mundo del nodo
+#[derive(Debug, Clone)]
+pub struct Drawable {
+    pub mesh: String,
+    pub part: usize,
+    pub material: String,
+    pub is_ring: bool,
+}
+
+pub struct Node {
+    pub parent: Option<NodeId>,
+    pub local: Transform,
+    pub drawables: Vec<Drawable>,
+    world: Mat4,
+}
+
+impl Node {
+    pub fn world_position(&self) -> Vec3A {
+        Vec3A::from(self.world.w_axis)
+    }
+}
+
+pub struct DrawItem<'a> {
+    pub model_matrix: Mat4,
+    pub drawable: &'a Drawable,
+}
+
+// Los nodos se guardan en orden de creación y un hijo siempre se crea después que su
+// padre, así que recorrerlos en orden propaga las matrices de arriba hacia abajo.
+#[derive(Default)]
+pub struct SceneGraph {
+    nodes: Vec<Node>,
+}
+
+impl SceneGraph {
+    pub fn new() -> Self {
+        SceneGraph::default()
+    }
+
+    pub fn add_node(&mut self, parent: Option<NodeId>, local: Transform) -> NodeId {
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            parent,
+            local,
+            drawables: Vec::new(),
+            world: Mat4::IDENTITY,
+        });
+        id
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        &mut self.nodes[id]
+    }
+
+    pub fn update_world(&mut self) {
+        for id in 0..self.nodes.len() {
+            let local = self.nodes[id].local.matrix();
+            self.nodes[id].world = match self.nodes[id].parent {
+                Some(parent) => self.nodes[parent].world * local,
+                None => local,
+            };
+        }
+    }
+
+    // Lista de dibujo en el orden de los nodos (y de sus submallas)
+    pub fn draw_list(&self) -> Vec<DrawItem<'_>> {
+        self.nodes
+            .iter()
+            .flat_map(|node| {
+                node.drawables.iter().map(move |drawable| DrawItem { model_matrix: node.world, drawable })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Quat;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn drawable(mesh: &str) -> Drawable {
+        Drawable { mesh: mesh.to_string(), part: 0, material: mesh.to_string(), is_ring: false }
+    }
+
+    // Un hijo hereda la traslación y la rotación del padre; la escala del nieto no afecta a su posición
+    #[test]
+    fn world_matrices_propagate_down() {
+        let mut graph = SceneGraph::new();
+        let spin = Quat::from_rotation_y(FRAC_PI_2);
+        let root = graph.add_node(None, Transform::default().with_rotation(spin));
+        graph.node_mut(root).local.translation = Vec3A::new(1.0, 2.0, 3.0);
+        let child = graph.add_node(Some(root), Transform::default());
+        graph.node_mut(child).local.translation = Vec3A::new(2.0, 0.0, 0.0);
+        let grandchild = graph.add_node(Some(child), Transform::default().with_non_uniform_scale(Vec3A::new(5.0, 5.0, 5.0)));
+        graph.update_world();
+
+        let expected = Vec3A::new(1.0, 2.0, 1.0);
+        assert!((graph.node(child).world_position() - expected).length() < 1e-5);
+        assert!((graph.node(grandchild).world_position() - expected).length() < 1e-5);
+    }
+
+    #[test]
+    fn draw_list_follows_node_order() {
+        let mut graph = SceneGraph::new();
+        let a = graph.add_node(None, Transform::default());
+        let b = graph.add_node(Some(a), Transform::default());
+        graph.node_mut(b).drawables = vec![drawable("b0"), drawable("b1")];
+        graph.node_mut(a).drawables = vec![drawable("a")];
+        graph.update_world();
+
+        let meshes: Vec<&str> = graph.draw_list().iter().map(|item| item.drawable.mesh.as_str()).collect();
+        assert_eq!(meshes, ["a", "b0", "b1"]);
+    }
+}
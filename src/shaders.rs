@@ -1,142 +1,98 @@
 use crate::Uniforms;
-use crate::fragment::Fragment;
+use crate::fragment::{spherical_uv, Fragment};
 use crate::vertex::Vertex;
 use crate::simplex::Simplex;
-use crate::cellular::CellularNoise;
-use raylib::prelude::*;
-
-#[derive(Debug, Clone, Copy)]
-pub enum ShaderType {
-    Mars,
-    Mocca,
-    Sun,
-    Saturn,
-    SaturnRing,
-    Uranus,
-    Neptune,
-    Spaceship,
-}
-
-fn multiply_matrix_vector4(matrix: &Matrix, vector: &Vector4) -> Vector4 {
-    Vector4::new(
-        matrix.m0 * vector.x + matrix.m4 * vector.y + matrix.m8 * vector.z + matrix.m12 * vector.w,
-        matrix.m1 * vector.x + matrix.m5 * vector.y + matrix.m9 * vector.z + matrix.m13 * vector.w,
-        matrix.m2 * vector.x + matrix.m6 * vector.y + matrix.m10 * vector.z + matrix.m14 * vector.w,
-        matrix.m3 * vector.x + matrix.m7 * vector.y + matrix.m11 * vector.z + matrix.m15 * vector.w,
-    )
+use crate::noise::{Fractal, Noise, NoiseBasis};
+use crate::cellular::{CellularNoise, CellularSample, DistanceMetric};
+use crate::gradient::ColorRamp;
+use crate::planet::{PlanetClass, PlanetParams, RingParams};
+use crate::lighting::{LightingModel, SpecularModel};
+use crate::material::{Material, MaterialParams, MaterialRegistry};
+use crate::varyings::{Varying, Varyings};
+use glam::{Mat4, Vec2, Vec3A};
+use std::f32::consts::PI;
+
+// Dirección normalizada; las tangentes usan la matriz de modelo y las normales (o
+// gradientes) la matriz normal
+fn transform_direction(direction: Vec3A, matrix: &Mat4) -> Vec3A {
+    matrix.transform_vector3a(direction).normalize_or_zero()
 }
 
-fn transform_normal(normal: &Vector3, model_matrix: &Matrix) -> Vector3 {
-    let normal_vec4 = Vector4::new(normal.x, normal.y, normal.z, 0.0);
-    let transformed_normal_vec4 = multiply_matrix_vector4(model_matrix, &normal_vec4);
-
-    let mut transformed_normal = Vector3::new(
-        transformed_normal_vec4.x,
-        transformed_normal_vec4.y,
-        transformed_normal_vec4.z,
-    );
-
-    let length = (transformed_normal.x * transformed_normal.x
-        + transformed_normal.y * transformed_normal.y
-        + transformed_normal.z * transformed_normal.z)
-        .sqrt();
-
-    if length > 0.0 {
-        transformed_normal.x /= length;
-        transformed_normal.y /= length;
-        transformed_normal.z /= length;
+// Radios interior y exterior del anillo, en unidades del objeto
+const RING_INNER_RADIUS: f32 = 1.3;
+const RING_OUTER_RADIUS: f32 = 2.3;
+
+// Posición radial en el anillo según el vertex shader, de 0 (borde interior) a 1 (exterior)
+pub const RING_RADIAL: Varying = Varying::Custom(0);
+
+// Radial y ángulo del fragmento en el anillo, desde su posición en espacio objeto sobre el
+// disco (la de mundo incluye la órbita del planeta). El vértice del polo no tiene longitud y
+// cae en el borde con ángulo 0, así que sus triángulos cruzan el disco; ahí la radial del
+// vertex shader no coincide con la del lugar y el fragmento se descarta (None).
+fn ring_coordinates(fragment: &Fragment) -> Option<(f32, f32)> {
+    let position = fragment.object_position();
+    let radius = position.x.hypot(position.z);
+    let radial = ((radius - RING_INNER_RADIUS) / (RING_OUTER_RADIUS - RING_INNER_RADIUS)).clamp(0.0, 1.0);
+    if (radial - fragment.varyings.float(RING_RADIAL)).abs() > 0.1 {
+        return None;
     }
-
-    transformed_normal
+    Some((radial, position.x.atan2(position.z)))
 }
 
 pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
     let mut position = vertex.position;
+    let mut ring_radial = 0.0;
     if uniforms.is_ring {
-        let radius = (position.x * position.x + position.y * position.y + position.z * position.z).sqrt();
-        let nx = if radius > 0.0 { position.x / radius } else { 0.0 };
-        let ny = if radius > 0.0 { position.y / radius } else { 0.0 };
-        let nz = if radius > 0.0 { position.z / radius } else { 0.0 };
-
-        let longitude = nx.atan2(nz);
-        let latitude = ny.asin();
+        // La esfera se despliega en un disco: la longitud da el ángulo y la latitud el radio
+        let n = position.normalize_or_zero();
+        let longitude = n.x.atan2(n.z);
+        let latitude = n.y.clamp(-1.0, 1.0).asin();
 
-        let inner_radius = 1.3;
-        let outer_radius = 2.3;
+        let t = ((latitude + PI / 2.0) / PI).clamp(0.0, 1.0);
+        let ring_radius = RING_INNER_RADIUS + t * (RING_OUTER_RADIUS - RING_INNER_RADIUS);
+        ring_radial = t;
 
-        let t = (latitude + std::f32::consts::PI / 2.0) / std::f32::consts::PI;
-        let t = t.clamp(0.0, 1.0);
-
-        let ring_radius = inner_radius + t * (outer_radius - inner_radius);
-
-        let x = ring_radius * longitude.cos();
-        let z = ring_radius * longitude.sin();
-        let y = 0.0;
-
-        position = Vector3::new(x, y, z);
+        position = Vec3A::new(ring_radius * longitude.cos(), 0.0, ring_radius * longitude.sin());
     }
 
-    let position_vec4 = Vector4::new(position.x, position.y, position.z, 1.0);
-    let world_position_vec4 = multiply_matrix_vector4(&uniforms.model_matrix, &position_vec4);
-    let world_position = Vector3::new(
-        world_position_vec4.x,
-        world_position_vec4.y,
-        world_position_vec4.z,
-    );
-
-    let view_position = multiply_matrix_vector4(&uniforms.view_matrix, &world_position_vec4);
-    let clip_position = multiply_matrix_vector4(&uniforms.projection_matrix, &view_position);
+    let world_position = uniforms.model_matrix * position.extend(1.0);
+    let clip_position = uniforms.projection_matrix * (uniforms.view_matrix * world_position);
 
-    let ndc = if clip_position.w != 0.0 {
-        Vector3::new(
-            clip_position.x / clip_position.w,
-            clip_position.y / clip_position.w,
-            clip_position.z / clip_position.w,
-        )
-    } else {
-        Vector3::new(clip_position.x, clip_position.y, clip_position.z)
-    };
+    let ndc = if clip_position.w != 0.0 { clip_position / clip_position.w } else { clip_position };
+    let screen_position = uniforms.viewport_matrix * ndc.truncate().extend(1.0);
 
-    let ndc_vec4 = Vector4::new(ndc.x, ndc.y, ndc.z, 1.0);
-    let screen_position = multiply_matrix_vector4(&uniforms.viewport_matrix, &ndc_vec4);
+    let transformed_position = Vec3A::from(screen_position.truncate());
 
-    let transformed_position = Vector3::new(screen_position.x, screen_position.y, screen_position.z);
+    // El anillo se aplana en el plano XZ del objeto, así que su normal es el +Y del objeto
+    let object_normal = if uniforms.is_ring { Vec3A::Y } else { vertex.normal };
+    let transformed_normal = transform_direction(object_normal, &uniforms.normal_matrix);
 
-    let transformed_normal = if uniforms.is_ring {
-        Vector3::new(0.0, 1.0, 0.0)
+    // Tangente en la dirección de longitud creciente (cross(up, n))
+    let object_tangent = if vertex.normal.x.abs() + vertex.normal.z.abs() > 1e-6 {
+        Vec3A::new(vertex.normal.z, 0.0, -vertex.normal.x)
     } else {
-        transform_normal(&vertex.normal, &uniforms.model_matrix)
+        Vec3A::X
     };
+    let tangent = transform_direction(object_tangent, &uniforms.model_matrix);
 
-    let local_pos = vertex.position;
-    let r_sq = local_pos.x * local_pos.x + local_pos.y * local_pos.y + local_pos.z * local_pos.z;
-    let (u, v) = if r_sq > 1e-6 {
-        let r = r_sq.sqrt();
-        let nx = local_pos.x / r;
-        let ny = local_pos.y / r;
-        let nz = local_pos.z / r;
-        let latitude = ny.asin();
-        let longitude = nx.atan2(nz);
-        (
-            (longitude + std::f32::consts::PI) / (2.0 * std::f32::consts::PI),
-            (latitude + std::f32::consts::PI / 2.0) / std::f32::consts::PI,
-        )
-    } else {
-        (0.0, 0.0)
-    };
-
-    let mut color = vertex.color;
-    color.y = u;
-    color.z = v;
+    let mut varyings = Varyings::new();
+    varyings.set_vec2(Varying::Uv, vertex.tex_coords);
+    varyings.set_vec2(Varying::SphereUv, spherical_uv(vertex.position));
+    // En el anillo, la posición sobre el disco
+    varyings.set_vec3(Varying::ObjectPosition, position);
+    varyings.set_vec3(Varying::Color, vertex.color);
+    varyings.set_vec3(Varying::Tangent, tangent);
+    varyings.set_float(RING_RADIAL, ring_radial);
 
     Vertex {
         position: vertex.position,
         normal: vertex.normal,
         tex_coords: vertex.tex_coords,
-        color,
+        color: vertex.color,
         transformed_position,
         transformed_normal,
-        world_position,
+        world_position: Vec3A::from(world_position.truncate()),
+        varyings,
     }
 }
 
@@ -145,396 +101,1182 @@ fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
     t * t * (3.0 - 2.0 * t)
 }
 
-fn hash21(p: Vector2) -> f32 {
-    let p = Vector2::new(
-        p.x.sin() * 127.1 + p.y.cos() * 311.7,
-        p.x.cos() * 269.5 + p.y.sin() * 183.3,
-    );
-    (p.x.sin() * 43758.5453).fract()
+// Inclina la normal según el gradiente de una altura definida sobre la esfera unitaria.
+// Solo cuenta la parte tangente del gradiente; como toda covariante se lleva a mundo con la
+// matriz normal.
+fn bump_normal(normal: Vec3A, direction: Vec3A, gradient: Vec3A, strength: f32, normal_matrix: &Mat4) -> Vec3A {
+    let tangential = gradient - direction * gradient.dot(direction);
+    let length = tangential.length();
+    if length < 1e-6 {
+        return normal;
+    }
+    let world = transform_direction(tangential, normal_matrix) * length;
+    (normal - world * strength).normalize_or_zero()
 }
 
-fn noise2(p: Vector2) -> f32 {
-    let i = Vector2::new(p.x.floor(), p.y.floor());
-    let f = Vector2::new(p.x.fract(), p.y.fract());
-    let u = Vector2::new(
-        3.0 * f.x * f.x - 2.0 * f.x * f.x * f.x,
-        3.0 * f.y * f.y - 2.0 * f.y * f.y * f.y,
-    );
+fn mix(a: Vec3A, b: Vec3A, t: f32) -> Vec3A {
+    a + (b - a) * t
+}
 
-    let a = hash21(i);
-    let b = hash21(i + Vector2::new(1.0, 0.0));
-    let c = hash21(i + Vector2::new(0.0, 1.0));
-    let d = hash21(i + Vector2::new(1.0, 1.0));
+fn load_palette(name: &str) -> ColorRamp {
+    ColorRamp::load(&format!("assets/palettes/{name}.ramp")).expect("No se pudo cargar la paleta")
+}
 
-    let mix_ab = a + u.x * (b - a);
-    let mix_cd = c + u.x * (d - c);
-    mix_ab + u.y * (mix_cd - mix_ab)
+// fBm de ruido de valor en [0, 1] sobre el plano UV
+fn fbm_uv(noise: &Noise, p: Vec2, octaves: usize) -> f32 {
+    noise.fbm(Vec3A::new(p.x, p.y, 0.0), Fractal::new(octaves)) * 0.5 + 0.5
 }
 
-fn fbm2(p: Vector2, octaves: usize) -> f32 {
-    let mut value = 0.0;
-    let mut amplitude = 1.0;
-    let mut frequency = 1.0;
-    let p = p;
+// Perfil de un cráter: cuánto se hunde el fondo, cuánto brilla el borde y el halo de eyecta
+// alrededor. `size` en [0, 1) ordena los cráteres de menor a mayor.
+struct Crater {
+    floor: f32,
+    rim: f32,
+    halo: f32,
+    size: f32,
+}
 
-    for _ in 0..octaves {
-        value += amplitude * noise2(p * frequency);
-        amplitude *= 0.5;
-        frequency *= 2.0;
+// Solo una fracción `density` de las celdas de Worley tiene cráter
+fn crater(cellular: &CellularNoise, p: Vec3A, density: f32) -> Option<Crater> {
+    let cell = cellular.sample3d(p);
+    if cell.cell_value() >= density {
+        return None;
     }
-    value
+    let size = cell.cell_value() / density;
+    let radius = 0.25 + 0.15 * size;
+    Some(Crater {
+        floor: smoothstep(radius, radius * 0.6, cell.f1),
+        rim: smoothstep(radius * 0.7, radius, cell.f1) * smoothstep(radius * 1.3, radius, cell.f1),
+        halo: smoothstep(radius * 2.0, radius, cell.f1),
+        size,
+    })
 }
 
-pub fn mars_shader(fragment: &Fragment) -> Vector3 {
-    let desert      = Vector3::new(1.58, 0.29, 0.15);
-    let volcanic    = Vector3::new(2.49, 0.10, 0.10);
-    let dust        = Vector3::new(2.43, 1.26, 0.24);
-    let ice         = Vector3::new(0.91, 0.90, 0.88);
+pub fn register_builtin(registry: &mut MaterialRegistry) {
+    registry.register("mars", || Box::new(MarsMaterial::new()));
+    registry.register("mocca", || Box::new(MoccaMaterial::new()));
+    registry.register("sun", || Box::new(SunMaterial::new()));
+    registry.register("saturn", || Box::new(SaturnMaterial::new()));
+    registry.register("saturn_ring", || Box::new(SaturnRingMaterial::new()));
+    registry.register("uranus", || Box::new(UranusMaterial::new()));
+    registry.register("neptune", || Box::new(NeptuneMaterial::new()));
+    registry.register("spaceship", || Box::new(SpaceshipMaterial::new()));
+    for surface in MoonSurface::ALL {
+        registry.register(surface.name(), move || Box::new(MoonMaterial::new(surface)));
+    }
+}
 
-    let u = fragment.color.y;
-    let v = fragment.color.z;
-    let uv = Vector2::new(u, v);
+pub struct MarsMaterial {
+    params: MaterialParams,
+    terrain: ColorRamp,
+    noise: Noise,
+    relief: Noise,
+}
 
-    let base = fbm2(uv * 2.5, 3);
-    let red_spots = fbm2(uv * 4.0 + Vector2::new(10.0, 20.0), 2);
-    let dark_zones = fbm2(uv * 8.0 + Vector2::new(30.0, 40.0), 2);
-    let polar_blend = smoothstep(0.85, 1.0, v) + smoothstep(0.0, 0.15, v);
+impl MarsMaterial {
+    pub fn new() -> Self {
+        MarsMaterial {
+            params: MaterialParams::new()
+                .with_color("volcanic", Vec3A::new(2.49, 0.10, 0.10))
+                .with_color("ice", Vec3A::new(0.91, 0.90, 0.88))
+                .with_float("ice_specular", 0.45)
+                .with_float("ice_shininess", 48.0)
+                .with_float("relief_scale", 5.0)
+                .with_float("relief_strength", 0.03),
+            terrain: load_palette("mars"),
+            noise: Noise::new(NoiseBasis::Value, 11),
+            relief: Noise::new(NoiseBasis::Gradient, 21),
+        }
+    }
 
-    let mut color = if base > 0.6 {
-        dust
-    } else if base > 0.35 {
-        desert
-    } else {
-        desert * 0.9
-    };
+    fn polar_blend(v: f32) -> f32 {
+        smoothstep(0.85, 1.0, v) + smoothstep(0.0, 0.15, v)
+    }
+}
 
-    if red_spots > 0.85 { color = volcanic; }
-    if dark_zones < 0.15 { color = volcanic * 0.85; }
+impl Material for MarsMaterial {
+    fn name(&self) -> &str {
+        "mars"
+    }
+
+    fn surface_color(&self, fragment: &Fragment, _uniforms: &Uniforms) -> Vec3A {
+        let volcanic = self.params.color("volcanic", Vec3A::new(2.49, 0.10, 0.10));
+        let ice = self.params.color("ice", Vec3A::new(0.91, 0.90, 0.88));
+
+        let uv = fragment.sphere_uv();
+        let v = uv.y;
+
+        let base = fbm_uv(&self.noise, uv * 2.5, 3);
+        let red_spots = fbm_uv(&self.noise, uv * 4.0 + Vec2::new(10.0, 20.0), 2);
+        let dark_zones = fbm_uv(&self.noise, uv * 8.0 + Vec2::new(30.0, 40.0), 2);
+        let polar_blend = Self::polar_blend(v);
 
-    let syrtis_center = Vector2::new(0.3, 0.5);
-    let dist = (uv.x - syrtis_center.x).hypot(uv.y - syrtis_center.y);
-    let syrtis = smoothstep(0.12, 0.04, dist);
-    if syrtis > 0.7 {
-        color = volcanic * 1.1;
+        let mut color = self.terrain.sample(base);
+        color = mix(color, volcanic, smoothstep(0.65, 0.71, red_spots));
+        color = mix(color, volcanic * 0.85, smoothstep(0.13, 0.07, dark_zones));
+
+        let syrtis_center = Vec2::new(0.3, 0.5);
+        let dist = (uv.x - syrtis_center.x).hypot(uv.y - syrtis_center.y);
+        let syrtis = smoothstep(0.12, 0.04, dist);
+        color = mix(color, volcanic * 1.1, smoothstep(0.55, 0.85, syrtis));
+
+        if polar_blend > 0.1 {
+            color = color * (1.0 - polar_blend * 0.5) + ice * polar_blend * 0.5;
+        }
+
+        color
     }
 
-    if polar_blend > 0.1 {
-        color = color * (1.0 - polar_blend * 0.5) + ice * polar_blend * 0.5;
+    // Relieve de crestas y valles a partir del gradiente analítico del fBm
+    fn normal(&self, fragment: &Fragment, uniforms: &Uniforms) -> Vec3A {
+        let scale = self.params.float("relief_scale", 5.0);
+        let direction = fragment.object_position().normalize_or_zero();
+        let relief = self.relief.fbm_grad(direction * scale, Fractal::new(4));
+        bump_normal(
+            fragment.normal,
+            direction,
+            relief.gradient * scale,
+            self.params.float("relief_strength", 0.03),
+            &uniforms.normal_matrix,
+        )
     }
 
-    color
+    fn specular(&self, fragment: &Fragment, _uniforms: &Uniforms) -> SpecularModel {
+        let ice = Self::polar_blend(fragment.sphere_uv().y).min(1.0);
+        if ice <= 0.1 {
+            return SpecularModel::None;
+        }
+        SpecularModel::BlinnPhong {
+            shininess: self.params.float("ice_shininess", 48.0),
+            color: Vec3A::ONE * (self.params.float("ice_specular", 0.45) * ice),
+        }
+    }
+
+    fn params(&self) -> &MaterialParams {
+        &self.params
+    }
+
+    fn params_mut(&mut self) -> &mut MaterialParams {
+        &mut self.params
+    }
+}
+
+pub struct SaturnMaterial {
+    params: MaterialParams,
+    bands: ColorRamp,
+    noise: Noise,
 }
 
-pub fn saturn_shader(fragment: &Fragment) -> Vector3 {
-    let u = fragment.color.y;
-    let v = fragment.color.z;
-    let uv = Vector2::new(u, v);
+impl SaturnMaterial {
+    pub fn new() -> Self {
+        SaturnMaterial {
+            params: MaterialParams::new()
+                .with_float("band_wobble", 0.015),
+            bands: load_palette("saturn"),
+            noise: Noise::new(NoiseBasis::Value, 12),
+        }
+    }
+}
+
+impl Material for SaturnMaterial {
+    fn name(&self) -> &str {
+        "saturn"
+    }
 
-    let layer1 = Vector3::new(0.96, 0.92, 0.82);
-    let layer2 = Vector3::new(0.92, 0.85, 0.70);
-    let layer3 = Vector3::new(0.65, 0.52, 0.38);
+    fn surface_color(&self, fragment: &Fragment, _uniforms: &Uniforms) -> Vec3A {
+        let uv = fragment.sphere_uv();
+        let v = uv.y;
 
-    let detail = fbm2(uv * Vector2::new(2.0, 20.0), 2) * 0.03;
+        let turbulence = fbm_uv(&self.noise, uv * Vec2::new(2.0, 20.0), 2);
+        let detail = turbulence * 0.045;
 
-    let mut color = layer1;
+        // Los bordes de las bandas ondulan un poco con el mismo ruido
+        let wobble = (turbulence - 0.5) * self.params.float("band_wobble", 0.015);
+        let mut color = self.bands.sample(v + wobble);
 
-    if v > 0.4 && v < 0.6 { color = layer3; }
-    if v > 0.2 && v < 0.3 { color = layer2; }
-    if v > 0.7 && v < 0.8 { color = layer2; }
-    if v < 0.15 || v > 0.85 { color = layer3 * 0.8; }
+        color += Vec3A::new(detail, detail * 0.9, detail * 0.7);
 
-    color += Vector3::new(detail, detail * 0.9, detail * 0.7);
+        let equator_blend = 1.0 - (v - 0.5).abs() * 2.0;
+        color *= 1.0 + equator_blend * 0.15;
 
-    let equator_blend = 1.0 - (v - 0.5).abs() * 2.0;
-    color *= 1.0 + equator_blend * 0.15;
+        color
+    }
+
+    fn params(&self) -> &MaterialParams {
+        &self.params
+    }
 
-    color
+    fn params_mut(&mut self) -> &mut MaterialParams {
+        &mut self.params
+    }
 }
 
-pub fn saturn_ring_shader(fragment: &Fragment) -> Vector3 {
-    let x = fragment.world_position.x;
-    let z = fragment.world_position.z;
-    let radius = (x * x + z * z).sqrt();
+pub struct SaturnRingMaterial {
+    params: MaterialParams,
+}
 
-    let inner = 1.3;
-    let outer = 2.3;
-    let t = ((radius - inner) / (outer - inner)).clamp(0.0, 1.0);
+impl SaturnRingMaterial {
+    pub fn new() -> Self {
+        SaturnRingMaterial {
+            params: MaterialParams::new()
+                .with_color("inner", Vec3A::new(0.88, 0.80, 0.65))
+                .with_color("middle", Vec3A::new(0.75, 0.65, 0.50))
+                .with_color("outer", Vec3A::new(0.95, 0.90, 0.80)),
+        }
+    }
+}
 
-    let color = if t < 0.3 {
-        Vector3::new(0.88, 0.80, 0.65)
-    } else if t < 0.7 {
-        Vector3::new(0.75, 0.65, 0.50)
-    } else {
-        Vector3::new(0.95, 0.90, 0.80) 
-    };
-    if (t - 0.5).abs() < 0.05 {
-        return color * 0.4;
-    }
-    let angle = x.atan2(z);
-    let noise = ((angle * 10.0).sin() * 0.5 + 0.5) * 0.03;
-    (color + Vector3::new(noise, noise * 0.8, noise * 0.6)) * 0.7
-}
-
-pub fn mocca_shader(fragment: &Fragment) -> Vector3 {
-    let u = fragment.color.y;
-    let v = fragment.color.z;
-    let uv = Vector2::new(u, v);
-
-    let layer1 = Vector3::new(0.86, 0.70, 0.55);
-    let mid = fbm2(uv * 4.0 + Vector2::new(5.0, 10.0), 2);
-    let layer2 = Vector3::new(0.55, 0.36, 0.22);
-    let dark = fbm2(uv * 8.0 + Vector2::new(15.0, 25.0), 2);
-    let layer3 = Vector3::new(0.35, 0.22, 0.12);
-    let foam = fbm2(uv * 16.0 + Vector2::new(100.0, 200.0), 2);
-    let layer4 = Vector3::new(0.95, 0.92, 0.88);
-
-    let mut color = layer1;
-    if mid > 0.6 { color = layer2; }
-    if dark < 0.3 { color = layer3; }
-    if foam > 0.85 { color = color * 0.7 + layer4 * 0.3; }
-
-    let star = (
-        (uv.x * 6.0).sin() * (uv.y * 4.0).cos() +
-        (uv.x * 3.0 + 1.0).cos() * (uv.y * 5.0 + 2.0).sin()
-    ).abs() * 0.3;
-    if star > 0.7 {
-        color = Vector3::new(0.95, 0.90, 0.80);
-    }
-
-    color
-}
-
-pub fn sun_shader(fragment: &Fragment, time: f32) -> Vector3 {
-    let simplex = Simplex::new();
-    let cellular = CellularNoise::new();
-    
-    let u = fragment.color.y;
-    let v = fragment.color.z;
-    
-    let cell_scale = 8.0;
-    let cell_speed = 0.3;
-    let cells = cellular.cellular2d(
-        u * cell_scale + time * cell_speed,
-        v * cell_scale + time * cell_speed * 0.7
-    );
-    
-    let cell_edges = cellular.cellular2d_f2_f1(
-        u * cell_scale * 0.5 - time * cell_speed * 0.4,
-        v * cell_scale * 0.5 + time * cell_speed * 0.3
-    );
-    
-    let surface_scale = 6.0;
-    let surface_speed = 0.4;
-    let surface = simplex.fbm(
-        u * surface_scale + time * surface_speed,
-        v * surface_scale + time * surface_speed * 0.6,
-        3,
-        2.0,
-        0.5
-    );
-    
-    let turbulence_scale = 10.0;
-    let turbulence_speed = 0.5;
-    let turbulence = simplex.fbm(
-        u * turbulence_scale - time * turbulence_speed * 0.6,
-        v * turbulence_scale + time * turbulence_speed * 0.8,
-        4,
-        2.2,
-        0.45
-    );
-    
-    let wave_scale = 2.5;
-    let wave_speed = 0.25;
-    let waves = simplex.noise2d(
-        u * wave_scale + time * wave_speed,
-        v * wave_scale - time * wave_speed * 1.2
-    );
-    
-    let base_yellow = Vector3::new(1.0, 0.88, 0.25);
-    let bright_yellow = Vector3::new(1.0, 0.95, 0.40);
-    let warm_yellow = Vector3::new(1.0, 0.80, 0.18);
-    let golden = Vector3::new(0.95, 0.70, 0.12);
-    let hot_spot = Vector3::new(1.0, 0.98, 0.55);
-    
-    let cell_norm = (1.0 - cells).clamp(0.0, 1.0);
-    let mut color = base_yellow;
-    
-    if cell_norm > 0.7 {
-        let intensity = smoothstep(0.7, 0.9, cell_norm);
-        color = color * (1.0 - intensity * 0.6) + hot_spot * intensity * 0.6;
-    }
-    
-    let edge_norm = (cell_edges * 2.0).clamp(0.0, 1.0);
-    if edge_norm > 0.4 {
-        let intensity = smoothstep(0.4, 0.7, edge_norm);
-        color = color * (1.0 - intensity * 0.4) + golden * intensity * 0.4;
-    }
-    
-    let surface_norm = surface * 0.5 + 0.5;
-    color = color * (0.8 + surface_norm * 0.4);
-    
-    let turb_norm = turbulence * 0.5 + 0.5;
-    if turb_norm > 0.6 {
-        let blend = smoothstep(0.6, 0.8, turb_norm) * 0.5;
-        color = color * (1.0 - blend) + bright_yellow * blend;
-    } else if turb_norm < 0.4 {
-        let blend = smoothstep(0.4, 0.2, turb_norm) * 0.4;
-        color = color * (1.0 - blend) + warm_yellow * blend;
-    }
-    
-    let wave_norm = waves * 0.5 + 0.5;
-    let wave_intensity = wave_norm * 0.15;
-    color = color * (1.0 + wave_intensity);
-    
-    let global_pulse = ((time * 0.8).sin() * 0.5 + 0.5) * 0.25;
-    color = color * (1.0 + global_pulse);
-    
-    let cell_pulse = ((time * 1.5 + cell_norm * 3.14).sin() * 0.5 + 0.5) * 0.2;
-    color = color * (1.0 + cell_pulse);
-    
-    color.x = color.x.clamp(0.0, 1.0);
-    color.y = color.y.clamp(0.0, 1.0);
-    color.z = color.z.clamp(0.0, 1.0);
-    
-    color
-}
-
-pub fn uranus_shader(fragment: &Fragment) -> Vector3 {
-    let u = fragment.color.y;
-    let v = fragment.color.z;
-    let uv = Vector2::new(u, v);
-
-    // Base cyan/azul claro (Urano)
-    let base = Vector3::new(0.65, 0.85, 0.92);
-    let band1 = Vector3::new(0.55, 0.75, 0.88);
-    let band2 = Vector3::new(0.45, 0.65, 0.80);
-    let haze = Vector3::new(0.85, 0.92, 0.98);
-
-    let noise1 = fbm2(uv * 4.0 + Vector2::new(5.0, 10.0), 2);
-    let noise2 = fbm2(uv * 8.0 + Vector2::new(15.0, 25.0), 2);
-    let noise3 = fbm2(uv * 16.0 + Vector2::new(100.0, 200.0), 2);
-
-    let mut color = base;
-
-    // bandas ecuatoriales más oscuras
-    if noise1 > 0.65 { color = band1; }
-    if noise2 < 0.35 { color = band2; }
-
-    // toques de bruma polar/clara
-    let polar_blend = smoothstep(0.85, 1.0, v.abs() - 0.5) + smoothstep(0.0, 0.15, v);
-    if polar_blend > 0.2 {
-        color = color * (1.0 - polar_blend * 0.3) + haze * polar_blend * 0.3;
-    }
-
-    // textura de nubes suaves
-    let cloud = fbm2(uv * 20.0, 2);
-    if cloud > 0.8 {
-        color = color * 0.9 + haze * 0.1;
-    }
-
-    color
-}
-
-pub fn neptune_shader(fragment: &Fragment) -> Vector3 {
-    let u = fragment.color.y;
-    let v = fragment.color.z;
-    let uv = Vector2::new(u, v);
-
-    // Más oscuro y azul intenso (Neptuno)
-    let base = Vector3::new(0.35, 0.55, 0.95);
-    let storm = Vector3::new(0.25, 0.40, 0.80);  // Gran Mancha Oscura
-    let bright_spot = Vector3::new(0.80, 0.90, 1.0);
-
-    let noise1 = fbm2(uv * 4.0, 3);
-    let noise2 = fbm2(uv * 8.0 + Vector2::new(30.0, 40.0), 3);
-
-    let mut color = base;
-
-    // bandas más marcadas
-    if noise1 > 0.7 { color = base * 0.85; }
-    if noise2 < 0.2 { color = storm; }
-
-    // simular la Gran Mancha Oscura (como en Júpiter pero azul)
-    let storm_center = Vector2::new(0.65, 0.4);
-    let dist = (uv.x - storm_center.x).hypot(uv.y - storm_center.y);
-    let storm_effect = smoothstep(0.15, 0.05, dist);
-    if storm_effect > 0.6 {
-        color = storm * (1.0 - storm_effect * 0.3) + base * storm_effect * 0.3;
-    }
-
-    // toques brillantes (nubes altas de metano)
-    let high_clouds = fbm2(uv * 25.0 + Vector2::new(100.0, 200.0), 2);
-    if high_clouds > 0.92 {
-        color = color * 0.7 + bright_spot * 0.3;
-    }
-
-    color
-}
-
-pub fn spaceship_shader(fragment: &Fragment, time: f32) -> Vector3 {
-    // Color base metálico plateado/gris
-    let metal_base = Vector3::new(0.7, 0.75, 0.8);
-    let metal_dark = Vector3::new(0.3, 0.35, 0.4);
-    let accent_blue = Vector3::new(0.2, 0.5, 1.0);
-    let accent_red = Vector3::new(1.0, 0.2, 0.2);
-    
-    let u = fragment.color.y;
-    let v = fragment.color.z;
-    let uv = Vector2::new(u, v);
-    
-    // Panel pattern
-    let panel_noise = fbm2(uv * 8.0, 2);
-    
-    let mut color = if panel_noise > 0.6 {
-        metal_base
-    } else {
-        metal_dark
-    };
-    
-    // Líneas de detalles
-    let lines = ((uv.x * 20.0).sin().abs() + (uv.y * 15.0).sin().abs()) * 0.5;
-    if lines > 0.9 {
-        color = color * 0.8;
-    }
-    
-    // Luces parpadeantes (ventanas o motores)
-    let light_pulse = (time * 3.0).sin() * 0.5 + 0.5;
-    let window_pattern = fbm2(uv * 30.0 + Vector2::new(100.0, 100.0), 1);
-    
-    if window_pattern > 0.95 {
-        color = color * 0.3 + accent_blue * 0.7 * light_pulse;
-    } else if window_pattern > 0.93 {
-        color = color * 0.3 + accent_red * 0.7 * light_pulse;
-    }
-    
-    // Reflejo especular sutil
-    let normal_y = fragment.normal.y;
-    let specular = (normal_y * 0.5 + 0.5).powf(2.0) * 0.2;
-    color = color + Vector3::new(specular, specular, specular);
-    
-    color
-}
-
-pub fn fragment_shaders(
-    fragment: &Fragment,
-    _uniforms: &Uniforms,
-    shader_type: ShaderType,
-    time: f32,
-) -> Vector3 {
-    let base_color = match shader_type {
-        ShaderType::Mars => mars_shader(fragment),
-        ShaderType::Mocca => mocca_shader(fragment),
-        ShaderType::Sun => sun_shader(fragment, time),
-        ShaderType::Saturn => saturn_shader(fragment),
-        ShaderType::SaturnRing => saturn_ring_shader(fragment),
-        ShaderType::Uranus => uranus_shader(fragment),
-        ShaderType::Neptune => neptune_shader(fragment),
-        ShaderType::Spaceship => spaceship_shader(fragment, time),
-    };
+impl Material for SaturnRingMaterial {
+    fn name(&self) -> &str {
+        "saturn_ring"
+    }
+
+    fn surface_color(&self, fragment: &Fragment, _uniforms: &Uniforms) -> Vec3A {
+        let Some((t, angle)) = ring_coordinates(fragment) else {
+            return Vec3A::ZERO;
+        };
+
+        let color = if t < 0.3 {
+            self.params.color("inner", Vec3A::new(0.88, 0.80, 0.65))
+        } else if t < 0.7 {
+            self.params.color("middle", Vec3A::new(0.75, 0.65, 0.50))
+        } else {
+            self.params.color("outer", Vec3A::new(0.95, 0.90, 0.80))
+        };
+        if (t - 0.5).abs() < 0.05 {
+            return color * 0.4;
+        }
+        let noise = ((angle * 10.0).sin() * 0.5 + 0.5) * 0.03;
+        (color + Vec3A::new(noise, noise * 0.8, noise * 0.6)) * 0.7
+    }
+
+    fn opacity(&self, fragment: &Fragment, _uniforms: &Uniforms) -> f32 {
+        if ring_coordinates(fragment).is_some() { 1.0 } else { 0.0 }
+    }
+
+    fn params(&self) -> &MaterialParams {
+        &self.params
+    }
+
+    fn params_mut(&mut self) -> &mut MaterialParams {
+        &mut self.params
+    }
+}
+
+pub struct MoccaMaterial {
+    params: MaterialParams,
+    layers: ColorRamp,
+    noise: Noise,
+    foam: Noise,
+}
+
+impl MoccaMaterial {
+    pub fn new() -> Self {
+        MoccaMaterial {
+            params: MaterialParams::new()
+                .with_color("layer3", Vec3A::new(0.35, 0.22, 0.12))
+                .with_color("foam", Vec3A::new(0.95, 0.92, 0.88)),
+            layers: load_palette("mocca"),
+            noise: Noise::new(NoiseBasis::Value, 13),
+            foam: Noise::new(NoiseBasis::Worley, 13),
+        }
+    }
+}
+
+impl Material for MoccaMaterial {
+    fn name(&self) -> &str {
+        "mocca"
+    }
+
+    fn surface_color(&self, fragment: &Fragment, _uniforms: &Uniforms) -> Vec3A {
+        let uv = fragment.sphere_uv();
+
+        // Remolinos de la crema: fBm con el dominio deformado por otro fBm
+        let swirl = Vec3A::new(uv.x * 4.0 + 5.0, uv.y * 4.0 + 10.0, 0.0);
+        let mid = self.noise.warped_fbm(swirl, 0.6, Fractal::new(2)) * 0.5 + 0.5;
+        let dark = fbm_uv(&self.noise, uv * 8.0 + Vec2::new(15.0, 25.0), 2);
+        let layer3 = self.params.color("layer3", Vec3A::new(0.35, 0.22, 0.12));
+        // Burbujas de espuma: centros de las celdas de Worley
+        let bubbles = self.foam.fbm(Vec3A::new(uv.x * 16.0, uv.y * 16.0, 0.0), Fractal::new(2)) * 0.5 + 0.5;
+        let layer4 = self.params.color("foam", Vec3A::new(0.95, 0.92, 0.88));
+
+        let mut color = self.layers.sample(mid);
+        color = mix(color, layer3, smoothstep(0.23, 0.17, dark));
+        color = mix(color, layer4, smoothstep(0.3, 0.2, bubbles) * 0.3);
+
+        color
+    }
+
+    fn params(&self) -> &MaterialParams {
+        &self.params
+    }
+
+    fn params_mut(&mut self) -> &mut MaterialParams {
+        &mut self.params
+    }
+}
+
+// El Sol no se ilumina: todo su color sale como emisión
+pub struct SunMaterial {
+    params: MaterialParams,
+    simplex: Simplex,
+    cellular: CellularNoise,
+}
 
-    if matches!(shader_type, ShaderType::Sun) {
-        return base_color;
+impl SunMaterial {
+    pub fn new() -> Self {
+        SunMaterial {
+            params: MaterialParams::new()
+                .with_float("cell_scale", 3.0)
+                .with_float("cell_speed", 0.3)
+                .with_float("pulse", 0.25)
+                .with_color("base", Vec3A::new(1.0, 0.88, 0.25))
+                .with_color("bright", Vec3A::new(1.0, 0.95, 0.40))
+                .with_color("warm", Vec3A::new(1.0, 0.80, 0.18))
+                .with_color("golden", Vec3A::new(0.95, 0.70, 0.12))
+                .with_color("hot_spot", Vec3A::new(1.0, 0.98, 0.55)),
+            simplex: Simplex::new(7),
+            cellular: CellularNoise::new(7),
+        }
     }
+}
+
+impl Material for SunMaterial {
+    fn name(&self) -> &str {
+        "sun"
+    }
+
+    fn surface_color(&self, _fragment: &Fragment, _uniforms: &Uniforms) -> Vec3A {
+        Vec3A::ZERO
+    }
+
+    fn emission(&self, fragment: &Fragment, uniforms: &Uniforms) -> Vec3A {
+        let simplex = &self.simplex;
+        let cellular = &self.cellular;
+        let time = uniforms.time;
+
+        // Granulado y turbulencia sobre la esfera unitaria; el tiempo es la cuarta dimensión
+        let direction = fragment.object_position().normalize_or_zero();
+
+        let cell_scale = self.params.float("cell_scale", 3.0);
+        let cell_speed = self.params.float("cell_speed", 0.3);
+        let granule = cellular.sample3d(
+            direction * cell_scale + Vec3A::new(time * cell_speed, 0.0, time * cell_speed * 0.7)
+        );
+
+        let cell_edges = cellular.sample3d(
+            direction * cell_scale * 0.5 + Vec3A::new(0.0, time * cell_speed * 0.3, -time * cell_speed * 0.4)
+        ).edge();
+
+        let surface_scale = 1.6;
+        let surface_speed = 0.4;
+        let surface = simplex.fbm4(direction * surface_scale, time * surface_speed, Fractal::new(3));
+
+        let turbulence_scale = 2.8;
+        let turbulence_speed = 0.5;
+        let turbulence = simplex.fbm4(
+            direction * turbulence_scale + Vec3A::new(17.0, 0.0, 0.0),
+            time * turbulence_speed,
+            Fractal::new(4).with_lacunarity(2.2).with_gain(0.45)
+        );
+
+        let wave_scale = 0.7;
+        let wave_speed = 0.25;
+        let waves = simplex.noise3d(
+            direction.x * wave_scale + time * wave_speed,
+            direction.y * wave_scale,
+            direction.z * wave_scale - time * wave_speed * 1.2
+        );
+
+        let base_yellow = self.params.color("base", Vec3A::new(1.0, 0.88, 0.25));
+        let bright_yellow = self.params.color("bright", Vec3A::new(1.0, 0.95, 0.40));
+        let warm_yellow = self.params.color("warm", Vec3A::new(1.0, 0.80, 0.18));
+        let golden = self.params.color("golden", Vec3A::new(0.95, 0.70, 0.12));
+        let hot_spot = self.params.color("hot_spot", Vec3A::new(1.0, 0.98, 0.55));
+
+        let cell_norm = (1.0 - granule.f1).clamp(0.0, 1.0);
+        // Cada gránulo tiene su propio brillo
+        let mut color = base_yellow * (0.92 + granule.cell_value() * 0.16);
+
+        if cell_norm > 0.7 {
+            let intensity = smoothstep(0.7, 0.9, cell_norm);
+            color = color * (1.0 - intensity * 0.6) + hot_spot * intensity * 0.6;
+        }
+
+        let edge_norm = (cell_edges * 2.0).clamp(0.0, 1.0);
+        if edge_norm > 0.4 {
+            let intensity = smoothstep(0.4, 0.7, edge_norm);
+            color = color * (1.0 - intensity * 0.4) + golden * intensity * 0.4;
+        }
+
+        let surface_norm = surface * 0.5 + 0.5;
+        color *= 0.8 + surface_norm * 0.4;
+
+        let turb_norm = turbulence * 0.5 + 0.5;
+        if turb_norm > 0.6 {
+            let blend = smoothstep(0.6, 0.8, turb_norm) * 0.5;
+            color = color * (1.0 - blend) + bright_yellow * blend;
+        } else if turb_norm < 0.4 {
+            let blend = smoothstep(0.4, 0.2, turb_norm) * 0.4;
+            color = color * (1.0 - blend) + warm_yellow * blend;
+        }
+
+        let wave_norm = waves * 0.5 + 0.5;
+        let wave_intensity = wave_norm * 0.15;
+        color *= 1.0 + wave_intensity;
+
+        let global_pulse = ((time * 0.8).sin() * 0.5 + 0.5) * self.params.float("pulse", 0.25);
+        color *= 1.0 + global_pulse;
+
+        let cell_pulse = ((time * 1.5 + cell_norm * PI).sin() * 0.5 + 0.5) * 0.2;
+        color *= 1.0 + cell_pulse;
+
+        color.x = color.x.clamp(0.0, 1.0);
+        color.y = color.y.clamp(0.0, 1.0);
+        color.z = color.z.clamp(0.0, 1.0);
+
+        color
+    }
+
+    fn lighting(&self) -> LightingModel {
+        LightingModel::Unlit
+    }
+
+    fn params(&self) -> &MaterialParams {
+        &self.params
+    }
+
+    fn params_mut(&mut self) -> &mut MaterialParams {
+        &mut self.params
+    }
+}
+
+pub struct UranusMaterial {
+    params: MaterialParams,
+    bands: ColorRamp,
+    noise: Noise,
+}
+
+impl UranusMaterial {
+    pub fn new() -> Self {
+        UranusMaterial {
+            params: MaterialParams::new()
+                .with_color("band2", Vec3A::new(0.45, 0.65, 0.80))
+                .with_color("haze", Vec3A::new(0.85, 0.92, 0.98)),
+            bands: load_palette("uranus"),
+            noise: Noise::new(NoiseBasis::Value, 14),
+        }
+    }
+}
+
+impl Material for UranusMaterial {
+    fn name(&self) -> &str {
+        "uranus"
+    }
+
+    fn surface_color(&self, fragment: &Fragment, _uniforms: &Uniforms) -> Vec3A {
+        let uv = fragment.sphere_uv();
+        let v = uv.y;
+
+        let band2 = self.params.color("band2", Vec3A::new(0.45, 0.65, 0.80));
+        let haze = self.params.color("haze", Vec3A::new(0.85, 0.92, 0.98));
 
-    base_color * fragment.color.x
+        let noise1 = fbm_uv(&self.noise, uv * 4.0 + Vec2::new(5.0, 10.0), 2);
+        let noise2 = fbm_uv(&self.noise, uv * 8.0 + Vec2::new(15.0, 25.0), 2);
+
+        // Base cyan/azul claro (Urano) con bandas ecuatoriales más oscuras
+        let mut color = self.bands.sample(noise1);
+        color = mix(color, band2, smoothstep(0.26, 0.2, noise2));
+
+        // toques de bruma polar/clara
+        let polar_blend = smoothstep(0.85, 1.0, v.abs() - 0.5) + smoothstep(0.0, 0.15, v);
+        if polar_blend > 0.2 {
+            color = color * (1.0 - polar_blend * 0.3) + haze * polar_blend * 0.3;
+        }
+
+        // textura de nubes suaves
+        let cloud = self.noise.turbulence(Vec3A::new(uv.x * 20.0, uv.y * 20.0, 0.0), Fractal::new(2));
+        color = mix(color, haze, smoothstep(0.4, 0.5, cloud) * 0.1);
+
+        color
+    }
+
+    fn params(&self) -> &MaterialParams {
+        &self.params
+    }
+
+    fn params_mut(&mut self) -> &mut MaterialParams {
+        &mut self.params
+    }
+}
+
+pub struct NeptuneMaterial {
+    params: MaterialParams,
+    noise: Noise,
+    clouds: Noise,
+}
+
+impl NeptuneMaterial {
+    pub fn new() -> Self {
+        NeptuneMaterial {
+            params: MaterialParams::new()
+                .with_color("base", Vec3A::new(0.35, 0.55, 0.95))
+                .with_color("storm", Vec3A::new(0.25, 0.40, 0.80))
+                .with_color("bright_spot", Vec3A::new(0.80, 0.90, 1.0))
+                .with_float("specular", 0.3)
+                .with_float("shininess", 24.0),
+            noise: Noise::new(NoiseBasis::Value, 15),
+            clouds: Noise::new(NoiseBasis::Simplex, 15),
+        }
+    }
+}
+
+impl Material for NeptuneMaterial {
+    fn name(&self) -> &str {
+        "neptune"
+    }
+
+    fn surface_color(&self, fragment: &Fragment, _uniforms: &Uniforms) -> Vec3A {
+        let uv = fragment.sphere_uv();
+
+        // Más oscuro y azul intenso (Neptuno)
+        let base = self.params.color("base", Vec3A::new(0.35, 0.55, 0.95));
+        let storm = self.params.color("storm", Vec3A::new(0.25, 0.40, 0.80));  // Gran Mancha Oscura
+        let bright_spot = self.params.color("bright_spot", Vec3A::new(0.80, 0.90, 1.0));
+
+        let noise1 = fbm_uv(&self.noise, uv * 4.0, 3);
+        let noise2 = fbm_uv(&self.noise, uv * 8.0 + Vec2::new(30.0, 40.0), 3);
+
+        let mut color = base;
+
+        // bandas más marcadas
+        if noise1 > 0.4 { color = base * 0.85; }
+        if noise2 < 0.11 { color = storm; }
+
+        // simular la Gran Mancha Oscura (como en Júpiter pero azul)
+        let storm_center = Vec2::new(0.65, 0.4);
+        let dist = (uv.x - storm_center.x).hypot(uv.y - storm_center.y);
+        let storm_effect = smoothstep(0.15, 0.05, dist);
+        if storm_effect > 0.6 {
+            color = storm * (1.0 - storm_effect * 0.3) + base * storm_effect * 0.3;
+        }
+
+        // toques brillantes (nubes altas de metano), con octavas más separadas y persistentes
+        let direction = fragment.object_position().normalize_or_zero();
+        let fractal = Fractal::new(3).with_lacunarity(2.6).with_gain(0.6);
+        let high_clouds = self.clouds.billow(direction * 6.0 + Vec3A::new(100.0, 200.0, 0.0), fractal) * 0.5 + 0.5;
+        if high_clouds > 0.55 {
+            color = color * 0.7 + bright_spot * 0.3;
+        }
+
+        color
+    }
+
+    fn specular(&self, _fragment: &Fragment, _uniforms: &Uniforms) -> SpecularModel {
+        SpecularModel::BlinnPhong {
+            shininess: self.params.float("shininess", 24.0),
+            color: Vec3A::ONE * self.params.float("specular", 0.3),
+        }
+    }
+
+    fn params(&self) -> &MaterialParams {
+        &self.params
+    }
+
+    fn params_mut(&mut self) -> &mut MaterialParams {
+        &mut self.params
+    }
+}
+
+pub struct SpaceshipMaterial {
+    params: MaterialParams,
+    noise: Noise,
+    panels: CellularNoise,
+}
+
+impl SpaceshipMaterial {
+    pub fn new() -> Self {
+        SpaceshipMaterial {
+            params: MaterialParams::new()
+                .with_color("metal_base", Vec3A::new(0.7, 0.75, 0.8))
+                .with_color("metal_dark", Vec3A::new(0.3, 0.35, 0.4))
+                .with_color("accent_blue", Vec3A::new(0.2, 0.5, 1.0))
+                .with_color("accent_red", Vec3A::new(1.0, 0.2, 0.2))
+                .with_float("blink_speed", 3.0)
+                .with_float("metallic", 0.25)
+                .with_float("roughness", 0.4),
+            noise: Noise::new(NoiseBasis::Value, 16),
+            // Puntos en el centro de cada celda y distancia de Chebyshev: paneles cuadrados
+            panels: CellularNoise::new(16).with_metric(DistanceMetric::Chebyshev).with_jitter(0.0),
+        }
+    }
+}
+
+impl Material for SpaceshipMaterial {
+    fn name(&self) -> &str {
+        "spaceship"
+    }
+
+    fn surface_color(&self, fragment: &Fragment, uniforms: &Uniforms) -> Vec3A {
+        // Color base metálico plateado/gris
+        let metal_base = self.params.color("metal_base", Vec3A::new(0.7, 0.75, 0.8));
+        let metal_dark = self.params.color("metal_dark", Vec3A::new(0.3, 0.35, 0.4));
+        let accent_blue = self.params.color("accent_blue", Vec3A::new(0.2, 0.5, 1.0));
+        let accent_red = self.params.color("accent_red", Vec3A::new(1.0, 0.2, 0.2));
+
+        let uv = fragment.sphere_uv();
+
+        // Panel pattern
+        let panel_noise = fbm_uv(&self.noise, uv * 8.0, 2);
+
+        let mut color = if panel_noise > 0.4 {
+            metal_base
+        } else {
+            metal_dark
+        };
+
+        // Juntas entre las placas del casco
+        let seams = self.panels.sample2d(uv.x * 20.0, uv.y * 15.0).edge();
+        if seams < 0.04 {
+            color *= 0.8;
+        }
+
+        // Luces parpadeantes (ventanas o motores)
+        let light_pulse = (uniforms.time * self.params.float("blink_speed", 3.0)).sin() * 0.5 + 0.5;
+        let window_pattern = fbm_uv(&self.noise, uv * 30.0 + Vec2::new(100.0, 100.0), 1);
+
+        if window_pattern > 0.95 {
+            color = color * 0.3 + accent_blue * 0.7 * light_pulse;
+        } else if window_pattern > 0.93 {
+            color = color * 0.3 + accent_red * 0.7 * light_pulse;
+        }
+
+        color
+    }
+
+    fn specular(&self, _fragment: &Fragment, _uniforms: &Uniforms) -> SpecularModel {
+        SpecularModel::Ggx {
+            metallic: self.params.float("metallic", 0.25),
+            roughness: self.params.float("roughness", 0.4),
+        }
+    }
+
+    fn params(&self) -> &MaterialParams {
+        &self.params
+    }
+
+    fn params_mut(&mut self) -> &mut MaterialParams {
+        &mut self.params
+    }
+}
+
+// Material genérico de los mundos generados: todo sale de `PlanetParams`
+pub struct PlanetMaterial {
+    params: MaterialParams,
+    planet: PlanetParams,
+    noise: Noise,
+    cellular: CellularNoise,
+}
+
+impl PlanetMaterial {
+    pub fn new(planet: PlanetParams) -> Self {
+        PlanetMaterial {
+            params: MaterialParams::new()
+                .with_float("terrain_scale", planet.terrain_scale)
+                .with_float("band_count", planet.band_count as f32)
+                .with_float("band_turbulence", planet.band_turbulence)
+                .with_float("polar_cap", planet.polar_cap)
+                .with_float("crater_density", planet.crater_density)
+                .with_float("crater_scale", 6.0)
+                .with_float("sea_level", planet.sea_level)
+                .with_color("cap", planet.cap_color)
+                .with_color("glow", planet.glow),
+            noise: Noise::new(NoiseBasis::Gradient, planet.seed),
+            cellular: CellularNoise::new(planet.seed.wrapping_add(1)),
+            planet,
+        }
+    }
+
+    // Altura del terreno en [0, 1] sobre la esfera unitaria; los mundos rocosos y desérticos
+    // suman cordilleras del multifractal con crestas
+    fn height(&self, direction: Vec3A) -> f32 {
+        let scale = self.params.float("terrain_scale", 2.5);
+        let mut height = self.noise.fbm(direction * scale, Fractal::new(5)) * 0.8 + 0.5;
+        if matches!(self.planet.class, PlanetClass::Rocky | PlanetClass::Desert) {
+            let ridges = self.noise.ridged(direction * scale * 0.8 + Vec3A::new(9.0, 4.0, 1.0), Fractal::new(4));
+            height += smoothstep(0.5, 0.9, ridges) * 0.2;
+        }
+        height.clamp(0.0, 1.0)
+    }
+
+    fn cap_blend(&self, direction: Vec3A) -> f32 {
+        let cap = self.params.float("polar_cap", 0.0);
+        if cap <= 0.0 {
+            return 0.0;
+        }
+        let edge = 1.0 - cap;
+        let wobble = self.noise.sample(direction * 6.0) * 0.04;
+        smoothstep(edge - 0.03, edge + 0.03, direction.y.abs() + wobble)
+    }
+
+    fn is_water(&self, direction: Vec3A) -> bool {
+        self.planet.class == PlanetClass::Ocean && self.height(direction) < self.params.float("sea_level", 0.0)
+    }
+}
+
+impl Material for PlanetMaterial {
+    fn name(&self) -> &str {
+        self.planet.class.name()
+    }
+
+    fn surface_color(&self, fragment: &Fragment, _uniforms: &Uniforms) -> Vec3A {
+        let direction = fragment.object_position().normalize_or_zero();
+
+        let mut color = if self.planet.class.is_banded() {
+            let bands = self.params.float("band_count", 6.0);
+            let turbulence = self.params.float("band_turbulence", 0.05);
+            // Las bandas se arrastran con un flujo curl, sin divergencia, como los vórtices reales;
+            // el rotacional llega a ±3,5, de ahí la escala
+            let flow = self.noise.curl(direction * 2.0, Fractal::new(2)) * (turbulence * 0.2);
+            let streaks = self.noise.sample(Vec3A::new(direction.x * 4.0, direction.y * 40.0, direction.z * 4.0)) * 0.05;
+            let t = ((direction.y + flow.y) * bands * PI).sin() * 0.5 + 0.5;
+            self.planet.palette.sample(t + streaks)
+        } else {
+            self.planet.palette.sample(self.height(direction))
+        };
+
+        // Cráteres: solo una fracción de las celdas tiene uno; fondo oscuro y borde claro
+        let density = self.params.float("crater_density", 0.0);
+        if density > 0.0 && !self.is_water(direction) {
+            let scale = self.params.float("crater_scale", 6.0);
+            if let Some(crater) = crater(&self.cellular, direction * scale, density) {
+                color = color * (1.0 - crater.floor * 0.3) * (1.0 + crater.rim * 0.2);
+            }
+        }
+
+        for storm in &self.planet.storms {
+            let angle = direction.dot(storm.center).clamp(-1.0, 1.0).acos();
+            let mask = smoothstep(storm.radius, storm.radius * 0.4, angle);
+            let swirl = (angle / storm.radius * 3.0 * PI).cos() * 0.5 + 0.5;
+            color = mix(color, storm.color * (0.85 + swirl * 0.15), mask);
+        }
+
+        mix(color, self.params.color("cap", Vec3A::ONE), self.cap_blend(direction))
+    }
+
+    // La lava brilla en las grietas entre celdas y en las zonas bajas
+    fn emission(&self, fragment: &Fragment, uniforms: &Uniforms) -> Vec3A {
+        let glow = self.params.color("glow", Vec3A::ZERO);
+        if glow.length() <= 0.0 {
+            return Vec3A::ZERO;
+        }
+        let direction = fragment.object_position().normalize_or_zero();
+        let cracks = self.cellular.sample3d(direction * 4.0).edge();
+        let lowlands = smoothstep(0.35, 0.2, self.height(direction));
+        let flicker = 0.85 + 0.15 * (uniforms.time * 2.0 + direction.x * 5.0).sin();
+        glow * (smoothstep(0.08, 0.0, cracks).max(lowlands) * flicker)
+    }
+
+    fn specular(&self, fragment: &Fragment, _uniforms: &Uniforms) -> SpecularModel {
+        let direction = fragment.object_position().normalize_or_zero();
+        let ice = self.cap_blend(direction).max((self.planet.class == PlanetClass::Ice) as u8 as f32 * 0.6);
+        if self.is_water(direction) {
+            return SpecularModel::BlinnPhong { shininess: 64.0, color: Vec3A::ONE * 0.6 };
+        }
+        if ice <= 0.1 {
+            return SpecularModel::None;
+        }
+        SpecularModel::BlinnPhong { shininess: 48.0, color: Vec3A::ONE * (0.4 * ice) }
+    }
+
+    fn params(&self) -> &MaterialParams {
+        &self.params
+    }
+
+    fn params_mut(&mut self) -> &mut MaterialParams {
+        &mut self.params
+    }
+}
+
+// Anillo de un mundo generado; se dibuja con `is_ring` como el de Saturno
+pub struct PlanetRingMaterial {
+    params: MaterialParams,
+    ring: RingParams,
+}
+
+impl PlanetRingMaterial {
+    pub fn new(ring: RingParams) -> Self {
+        PlanetRingMaterial {
+            params: MaterialParams::new().with_float("opacity", ring.opacity),
+            ring,
+        }
+    }
+}
+
+impl Material for PlanetRingMaterial {
+    fn name(&self) -> &str {
+        "planet_ring"
+    }
+
+    fn surface_color(&self, fragment: &Fragment, _uniforms: &Uniforms) -> Vec3A {
+        let Some((t, _)) = ring_coordinates(fragment) else {
+            return Vec3A::ZERO;
+        };
+        let ringlets = (t * 90.0).sin() * 0.5 + 0.5;
+        self.ring.ramp.sample(t) * (0.85 + ringlets * 0.15)
+    }
+
+    fn opacity(&self, fragment: &Fragment, _uniforms: &Uniforms) -> f32 {
+        let Some((t, _)) = ring_coordinates(fragment) else {
+            return 0.0;
+        };
+        let gap = self
+            .ring
+            .gaps
+            .iter()
+            .map(|position| smoothstep(0.02, 0.005, (t - position).abs()))
+            .fold(0.0, f32::max);
+        let edges = smoothstep(0.0, 0.05, t) * smoothstep(1.0, 0.95, t);
+        self.params.float("opacity", 0.7) * (1.0 - gap) * edges
+    }
+
+    fn params(&self) -> &MaterialParams {
+        &self.params
+    }
+
+    fn params_mut(&mut self) -> &mut MaterialParams {
+        &mut self.params
+    }
+}
+
+// Superficies de las lunas; cada una se registra como material con su propio nombre
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoonSurface {
+    Moon,
+    Phobos,
+    Deimos,
+    Io,
+    Europa,
+    Ganymede,
+    Callisto,
+    Titan,
+    Triton,
+}
+
+impl MoonSurface {
+    pub const ALL: [MoonSurface; 9] = [
+        MoonSurface::Moon,
+        MoonSurface::Phobos,
+        MoonSurface::Deimos,
+        MoonSurface::Io,
+        MoonSurface::Europa,
+        MoonSurface::Ganymede,
+        MoonSurface::Callisto,
+        MoonSurface::Titan,
+        MoonSurface::Triton,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MoonSurface::Moon => "moon",
+            MoonSurface::Phobos => "phobos",
+            MoonSurface::Deimos => "deimos",
+            MoonSurface::Io => "io",
+            MoonSurface::Europa => "europa",
+            MoonSurface::Ganymede => "ganymede",
+            MoonSurface::Callisto => "callisto",
+            MoonSurface::Titan => "titan",
+            MoonSurface::Triton => "triton",
+        }
+    }
+
+    // Todas exponen `crater_density`, `crater_scale` y `relief_strength`; el resto es propio
+    fn params(self) -> MaterialParams {
+        let params = MaterialParams::new();
+        match self {
+            MoonSurface::Moon => params
+                .with_color("highlands", Vec3A::new(0.62, 0.60, 0.57))
+                .with_color("maria", Vec3A::new(0.25, 0.25, 0.27))
+                .with_float("near_side_bias", 0.3)
+                .with_float("crater_density", 0.35)
+                .with_float("crater_scale", 8.0)
+                .with_float("relief_strength", 0.04),
+            MoonSurface::Phobos => params
+                .with_color("regolith", Vec3A::new(0.36, 0.31, 0.27))
+                .with_float("groove_strength", 0.2)
+                .with_float("crater_density", 0.55)
+                .with_float("crater_scale", 6.0)
+                .with_float("relief_strength", 0.08),
+            MoonSurface::Deimos => params
+                .with_color("regolith", Vec3A::new(0.50, 0.44, 0.38))
+                .with_color("dust", Vec3A::new(0.62, 0.56, 0.48))
+                .with_float("crater_density", 0.2)
+                .with_float("crater_scale", 5.0)
+                .with_float("relief_strength", 0.05),
+            MoonSurface::Io => params
+                .with_color("sulfur", Vec3A::new(0.88, 0.80, 0.36))
+                .with_color("orange", Vec3A::new(0.82, 0.52, 0.20))
+                .with_color("frost", Vec3A::new(0.93, 0.91, 0.80))
+                .with_color("plume", Vec3A::new(0.72, 0.28, 0.12))
+                .with_color("glow", Vec3A::new(1.0, 0.35, 0.05))
+                .with_float("volcano_density", 0.3)
+                .with_float("volcano_scale", 5.0)
+                .with_float("crater_density", 0.0)
+                .with_float("crater_scale", 6.0)
+                .with_float("relief_strength", 0.02),
+            MoonSurface::Europa => params
+                .with_color("ice", Vec3A::new(0.90, 0.87, 0.80))
+                .with_color("lineae", Vec3A::new(0.58, 0.38, 0.26))
+                .with_color("chaos", Vec3A::new(0.72, 0.58, 0.46))
+                .with_float("ice_specular", 0.3)
+                .with_float("crater_density", 0.03)
+                .with_float("crater_scale", 6.0)
+                .with_float("relief_strength", 0.01),
+            MoonSurface::Ganymede => params
+                .with_color("dark", Vec3A::new(0.36, 0.33, 0.29))
+                .with_color("grooved", Vec3A::new(0.66, 0.63, 0.58))
+                .with_color("frost", Vec3A::new(0.86, 0.86, 0.88))
+                .with_float("ice_specular", 0.25)
+                .with_float("crater_density", 0.3)
+                .with_float("crater_scale", 8.0)
+                .with_float("relief_strength", 0.03),
+            MoonSurface::Callisto => params
+                .with_color("dark", Vec3A::new(0.30, 0.27, 0.23))
+                .with_color("ejecta", Vec3A::new(0.74, 0.71, 0.67))
+                .with_float("crater_density", 0.65)
+                .with_float("crater_scale", 11.0)
+                .with_float("relief_strength", 0.04),
+            MoonSurface::Titan => params
+                .with_color("haze", Vec3A::new(0.80, 0.56, 0.24))
+                .with_color("dunes", Vec3A::new(0.58, 0.40, 0.20))
+                .with_color("hood", Vec3A::new(0.52, 0.40, 0.26))
+                .with_float("crater_density", 0.0)
+                .with_float("crater_scale", 6.0)
+                .with_float("relief_strength", 0.0),
+            MoonSurface::Triton => params
+                .with_color("terrain", Vec3A::new(0.64, 0.66, 0.60))
+                .with_color("cap", Vec3A::new(0.92, 0.81, 0.77))
+                .with_color("streaks", Vec3A::new(0.36, 0.31, 0.30))
+                .with_float("ice_specular", 0.35)
+                .with_float("crater_density", 0.05)
+                .with_float("crater_scale", 6.0)
+                .with_float("relief_strength", 0.02),
+        }
+    }
+}
+
+// Material de las lunas: el ruido y los cráteres son comunes, los colores y los rasgos
+// (mares, volcanes, grietas, casquetes) dependen de la superficie
+pub struct MoonMaterial {
+    surface: MoonSurface,
+    params: MaterialParams,
+    noise: Noise,
+    cellular: CellularNoise,
+    blocks: CellularNoise,
+}
+
+// Rasgos fijos en espacio objeto; +Z es la cara que mira al planeta si la luna está anclada
+const STICKNEY: Vec3A = Vec3A::new(0.35, -0.1, 0.93);
+const VALHALLA: Vec3A = Vec3A::new(-0.6, 0.3, 0.74);
+
+impl MoonMaterial {
+    pub fn new(surface: MoonSurface) -> Self {
+        let seed = 40 + surface as u64;
+        MoonMaterial {
+            surface,
+            params: surface.params(),
+            noise: Noise::new(NoiseBasis::Gradient, seed),
+            cellular: CellularNoise::new(seed + 100),
+            // La distancia de Manhattan da celdas de bordes rectos, como bloques de hielo rotos
+            blocks: CellularNoise::new(seed + 200).with_metric(DistanceMetric::Manhattan).with_jitter(0.7),
+        }
+    }
+
+    // `MoonSurface::params` crea todos los parámetros que se leen, así que el valor por
+    // defecto no se usa
+    fn float(&self, name: &str) -> f32 {
+        self.params.float(name, 0.0)
+    }
+
+    fn color(&self, name: &str) -> Vec3A {
+        self.params.color(name, Vec3A::ZERO)
+    }
+
+    // Ruido en [0, 1] con un desplazamiento para sacar canales independientes
+    fn layer(&self, direction: Vec3A, scale: f32, offset: f32, octaves: usize) -> f32 {
+        self.noise.fbm(direction * scale + Vec3A::new(offset, offset * 0.7, -offset), Fractal::new(octaves)) * 0.5 + 0.5
+    }
+
+    // Celda de un volcán de Io, si la hay en esta dirección
+    fn volcano(&self, direction: Vec3A) -> Option<CellularSample> {
+        let cell = self.cellular.sample3d(direction * self.float("volcano_scale") + Vec3A::new(7.0, 3.0, 5.0));
+        (cell.cell_value() < self.float("volcano_density")).then_some(cell)
+    }
+
+    // Hielo brillante en [0, 1]: toda Europa, los casquetes de Ganímedes y el polo sur de Tritón
+    fn ice(&self, direction: Vec3A) -> f32 {
+        let wobble = self.noise.sample(direction * 6.0) * 0.05;
+        match self.surface {
+            MoonSurface::Europa => 1.0,
+            MoonSurface::Ganymede => smoothstep(0.7, 0.82, direction.y.abs() + wobble),
+            MoonSurface::Triton => smoothstep(-0.05, -0.2, direction.y + wobble),
+            _ => 0.0,
+        }
+    }
+
+    fn base_color(&self, direction: Vec3A) -> Vec3A {
+        let detail = self.layer(direction, 6.0, 0.0, 4);
+        match self.surface {
+            // Los mares de basalto se concentran en la cara visible
+            MoonSurface::Moon => {
+                let basins = self.layer(direction, 1.6, 11.0, 3) + direction.z * self.float("near_side_bias");
+                let maria = smoothstep(0.62, 0.72, basins);
+                mix(self.color("highlands") * (0.8 + detail * 0.4), self.color("maria") * (0.9 + detail * 0.2), maria)
+            }
+            // Stickney, el cráter gigante, y los surcos paralelos que lo rodean
+            MoonSurface::Phobos => {
+                let angle = direction.dot(STICKNEY.normalize_or_zero()).clamp(-1.0, 1.0).acos();
+                let basin = smoothstep(0.5, 0.3, angle);
+                let rim = smoothstep(0.35, 0.5, angle) * smoothstep(0.65, 0.5, angle);
+                let grooves = smoothstep(0.7, 1.0, ((direction.y + (detail - 0.5) * 0.1) * 45.0).sin()) * smoothstep(0.4, 0.8, angle);
+                let color = self.color("regolith") * (0.75 + detail * 0.5);
+                color * (1.0 - basin * 0.3) * (1.0 + rim * 0.25) * (1.0 - grooves * self.float("groove_strength"))
+            }
+            // Regolito liso; el polvo claro rellena las depresiones
+            MoonSurface::Deimos => {
+                let dust = smoothstep(0.55, 0.7, self.layer(direction, 3.0, 23.0, 3));
+                mix(self.color("regolith") * (0.85 + detail * 0.3), self.color("dust"), dust * 0.6)
+            }
+            MoonSurface::Io => {
+                let mottling = self.layer(direction, 3.0, 31.0, 4);
+                let frost = smoothstep(0.58, 0.7, self.layer(direction, 2.0, 47.0, 3));
+                let mut color = mix(self.color("sulfur"), self.color("orange"), smoothstep(0.42, 0.62, mottling));
+                color = mix(color, self.color("frost"), frost * 0.7);
+                color = mix(color, self.color("orange") * 0.6, smoothstep(0.65, 0.9, direction.y.abs()));
+                // Bocas oscuras; las mayores dejan un anillo rojo de depósitos, como Pele
+                if let Some(vent) = self.volcano(direction) {
+                    if vent.cell_value() < self.float("volcano_density") * 0.3 {
+                        let ring = smoothstep(0.2, 0.28, vent.f1) * smoothstep(0.42, 0.32, vent.f1);
+                        color = mix(color, self.color("plume"), ring * 0.8);
+                    }
+                    color = mix(color, Vec3A::new(0.12, 0.08, 0.06), smoothstep(0.14, 0.06, vent.f1));
+                }
+                color * (0.9 + detail * 0.2)
+            }
+            // Hielo cruzado por grietas rojizas y manchas de terreno caótico
+            MoonSurface::Europa => {
+                // Las grietas siguen los ceros de dos capas de ruido a distinta escala
+                let lineae = |scale: f32, offset: f32| {
+                    let n = self.noise.fbm(direction * scale + Vec3A::new(offset, -offset, offset * 0.5), Fractal::new(2));
+                    smoothstep(0.03, 0.0, n.abs())
+                };
+                let cracks = lineae(2.5, 3.0).max(lineae(5.0, 17.0) * 0.6);
+                let chaos = smoothstep(0.6, 0.72, self.layer(direction, 1.5, 13.0, 3));
+                // En el terreno caótico el hielo está partido en bloques
+                let rafts = smoothstep(0.08, 0.0, self.blocks.sample3d(direction * 14.0).edge()) * chaos;
+                let color = mix(self.color("ice") * (0.92 + detail * 0.16), self.color("chaos"), chaos * 0.6);
+                mix(color, self.color("lineae"), cracks.max(rafts) * 0.8)
+            }
+            // Regiones oscuras antiguas entre franjas claras surcadas
+            MoonSurface::Ganymede => {
+                let grooved = smoothstep(0.42, 0.5, self.layer(direction, 1.8, 29.0, 4));
+                let grooves = (self.layer(direction, 3.0, 37.0, 2) * 60.0).sin() * 0.5 + 0.5;
+                let terrain = mix(
+                    self.color("dark") * (0.8 + detail * 0.4),
+                    self.color("grooved") * (0.9 + grooves * 0.15),
+                    grooved,
+                );
+                mix(terrain, self.color("frost"), self.ice(direction))
+            }
+            // Valhalla: una cuenca clara rodeada de anillos concéntricos
+            MoonSurface::Callisto => {
+                let angle = direction.dot(VALHALLA.normalize_or_zero()).clamp(-1.0, 1.0).acos();
+                let rings = ((angle * 45.0).cos() * 0.5 + 0.5) * smoothstep(0.9, 0.15, angle);
+                let center = smoothstep(0.2, 0.05, angle);
+                let color = self.color("dark") * (0.8 + detail * 0.4);
+                mix(color, self.color("ejecta"), rings * 0.25 + center * 0.6)
+            }
+            // Solo se ve la neblina: bandas suaves, campos de dunas y la capucha polar del norte
+            MoonSurface::Titan => {
+                let bands = 1.0 + ((direction.y + (detail - 0.5) * 0.1) * 7.0).sin() * 0.04;
+                let dunes = smoothstep(0.5, 0.62, detail) * smoothstep(0.5, 0.2, direction.y.abs());
+                let color = mix(self.color("haze") * bands, self.color("dunes"), dunes * 0.25);
+                mix(color, self.color("hood"), smoothstep(0.6, 0.85, direction.y))
+            }
+            // Casquete rosado con vetas oscuras de los géiseres y "piel de melón" al norte
+            MoonSurface::Triton => {
+                let warped = direction * 9.0 + Vec3A::new(detail, detail, detail) * 1.5;
+                let dimples = smoothstep(0.1, 0.0, self.cellular.sample3d(warped).edge());
+                let terrain = self.color("terrain") * (0.9 + detail * 0.2) * (1.0 - dimples * 0.1);
+                let cap = self.ice(direction);
+                // El viento estira las vetas en una misma dirección
+                let stretched = Vec3A::new(direction.x * 3.0, direction.y * 12.0, direction.z * 12.0);
+                let plumes = self.noise.fbm(stretched + Vec3A::new(5.0, 9.0, 1.0), Fractal::new(2));
+                let streaks = smoothstep(0.25, 0.4, plumes) * cap;
+                mix(mix(terrain, self.color("cap"), cap), self.color("streaks"), streaks * 0.7)
+            }
+        }
+    }
+}
+
+impl Material for MoonMaterial {
+    fn name(&self) -> &str {
+        self.surface.name()
+    }
+
+    // Los cráteres más chicos conservan un halo de eyecta claro
+    fn surface_color(&self, fragment: &Fragment, _uniforms: &Uniforms) -> Vec3A {
+        let direction = fragment.object_position().normalize_or_zero();
+        let color = self.base_color(direction);
+        let scale = self.float("crater_scale");
+        match crater(&self.cellular, direction * scale, self.float("crater_density")) {
+            Some(crater) => {
+                let color = color * (1.0 - crater.floor * 0.3) * (1.0 + crater.rim * 0.25);
+                color * (1.0 + crater.halo * smoothstep(0.3, 0.0, crater.size) * 0.35)
+            }
+            None => color,
+        }
+    }
+
+    // Las bocas de los volcanes de Io brillan
+    fn emission(&self, fragment: &Fragment, uniforms: &Uniforms) -> Vec3A {
+        if self.surface != MoonSurface::Io {
+            return Vec3A::ZERO;
+        }
+        let direction = fragment.object_position().normalize_or_zero();
+        let Some(vent) = self.volcano(direction) else {
+            return Vec3A::ZERO;
+        };
+        let flicker = 0.8 + 0.2 * (uniforms.time * 3.0 + vent.cell_value() * 40.0).sin();
+        self.color("glow") * (smoothstep(0.07, 0.0, vent.f1) * flicker)
+    }
+
+    fn normal(&self, fragment: &Fragment, uniforms: &Uniforms) -> Vec3A {
+        let strength = self.float("relief_strength");
+        if strength <= 0.0 {
+            return fragment.normal;
+        }
+        let direction = fragment.object_position().normalize_or_zero();
+        let relief = self.noise.fbm_grad(direction * 5.0, Fractal::new(4));
+        bump_normal(fragment.normal, direction, relief.gradient * 5.0, strength, &uniforms.normal_matrix)
+    }
+
+    fn specular(&self, fragment: &Fragment, _uniforms: &Uniforms) -> SpecularModel {
+        let ice = self.ice(fragment.object_position().normalize_or_zero());
+        if ice <= 0.1 {
+            return SpecularModel::None;
+        }
+        SpecularModel::BlinnPhong {
+            shininess: 40.0,
+            color: Vec3A::ONE * (self.float("ice_specular") * ice),
+        }
+    }
+
+    fn params(&self) -> &MaterialParams {
+        &self.params
+    }
+
+    fn params_mut(&mut self) -> &mut MaterialParams {
+        &mut self.params
+    }
 }
@@ -1,31 +1,45 @@
-pub struct Simplex {}
+use crate::noise::{Fractal, Permutation};
+use glam::Vec3A;
+
+// Gradientes hacia las aristas de un cubo (2D usa solo x, y)
+pub const GRAD3: [[f32; 3]; 12] = [
+    [1.0, 1.0, 0.0], [-1.0, 1.0, 0.0], [1.0, -1.0, 0.0], [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0], [-1.0, 0.0, 1.0], [1.0, 0.0, -1.0], [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0], [0.0, -1.0, 1.0], [0.0, 1.0, -1.0], [0.0, -1.0, -1.0],
+];
+
+const GRAD4: [[f32; 4]; 32] = [
+    [0.0, 1.0, 1.0, 1.0], [0.0, 1.0, 1.0, -1.0], [0.0, 1.0, -1.0, 1.0], [0.0, 1.0, -1.0, -1.0],
+    [0.0, -1.0, 1.0, 1.0], [0.0, -1.0, 1.0, -1.0], [0.0, -1.0, -1.0, 1.0], [0.0, -1.0, -1.0, -1.0],
+    [1.0, 0.0, 1.0, 1.0], [1.0, 0.0, 1.0, -1.0], [1.0, 0.0, -1.0, 1.0], [1.0, 0.0, -1.0, -1.0],
+    [-1.0, 0.0, 1.0, 1.0], [-1.0, 0.0, 1.0, -1.0], [-1.0, 0.0, -1.0, 1.0], [-1.0, 0.0, -1.0, -1.0],
+    [1.0, 1.0, 0.0, 1.0], [1.0, 1.0, 0.0, -1.0], [1.0, -1.0, 0.0, 1.0], [1.0, -1.0, 0.0, -1.0],
+    [-1.0, 1.0, 0.0, 1.0], [-1.0, 1.0, 0.0, -1.0], [-1.0, -1.0, 0.0, 1.0], [-1.0, -1.0, 0.0, -1.0],
+    [1.0, 1.0, 1.0, 0.0], [1.0, 1.0, -1.0, 0.0], [1.0, -1.0, 1.0, 0.0], [1.0, -1.0, -1.0, 0.0],
+    [-1.0, 1.0, 1.0, 0.0], [-1.0, 1.0, -1.0, 0.0], [-1.0, -1.0, 1.0, 0.0], [-1.0, -1.0, -1.0, 0.0],
+];
+
+// Factores de sesgo (skew) y des-sesgo: (sqrt(n + 1) - 1) / n y (n + 1 - sqrt(n + 1)) / (n (n + 1))
+const F2: f32 = 0.366_025_4;
+const G2: f32 = 0.211_324_87;
+const F3: f32 = 1.0 / 3.0;
+const G3: f32 = 1.0 / 6.0;
+const F4: f32 = 0.309_017;
+const G4: f32 = 0.138_196_6;
+
+// Ruido simplex de Perlin/Gustavson con tabla de permutación por semilla
+pub struct Simplex {
+    perm: Permutation,
+}
 
 impl Simplex {
-    pub fn new() -> Self {
-        Simplex {}
-    }
-
-    fn hash(x: i32, y: i32) -> u8 {
-        let mut h = x as u32;
-        h = h.wrapping_mul(374761393).wrapping_add(y as u32);
-        h ^= h >> 13;
-        h = h.wrapping_mul(1274126177);
-        ((h ^ (h >> 16)) & 0xFF) as u8
-    }
-
-    fn grad(hash: u8, x: f32, y: f32) -> f32 {
-        match hash & 0x3 {
-            0 =>  x + y,
-            1 => -x + y,
-            2 =>  x - y,
-            _ => -x - y,
-        }
+    pub fn new(seed: u64) -> Self {
+        Simplex { perm: Permutation::new(seed) }
     }
 
+    // Ningún material muestrea ya en 2D (la UV tiene costura); se conserva para quien lo necesite
+    #[allow(dead_code)]
     pub fn noise2d(&self, x: f32, y: f32) -> f32 {
-        const F2: f32 = 0.366025403;
-        const G2: f32 = 0.211324865;
-
         let s = (x + y) * F2;
         let i = (x + s).floor() as i32;
         let j = (y + s).floor() as i32;
@@ -41,48 +55,175 @@ impl Simplex {
         let x2 = x0 - 1.0 + 2.0 * G2;
         let y2 = y0 - 1.0 + 2.0 * G2;
 
-        let gi0 = Self::hash(i,     j    );
-        let gi1 = Self::hash(i+i1, j+j1 );
-        let gi2 = Self::hash(i+1,  j+1  );
+        let corners = [
+            (x0, y0, self.perm.hash2(i, j)),
+            (x1, y1, self.perm.hash2(i + i1, j + j1)),
+            (x2, y2, self.perm.hash2(i + 1, j + 1)),
+        ];
 
-        let mut n0 = 0.0;
-        let mut n1 = 0.0;
-        let mut n2 = 0.0;
-
-        let t0 = 0.5 - x0*x0 - y0*y0;
-        if t0 > 0.0 {
-            let t0_2 = t0 * t0;
-            n0 = t0_2 * t0_2 * Self::grad(gi0, x0, y0);
+        let mut total = 0.0;
+        for (cx, cy, hash) in corners {
+            let t = 0.5 - cx * cx - cy * cy;
+            if t > 0.0 {
+                let g = GRAD3[hash % 12];
+                let t2 = t * t;
+                total += t2 * t2 * (g[0] * cx + g[1] * cy);
+            }
         }
 
-        let t1 = 0.5 - x1*x1 - y1*y1;
-        if t1 > 0.0 {
-            let t1_2 = t1 * t1;
-            n1 = t1_2 * t1_2 * Self::grad(gi1, x1, y1);
-        }
+        70.0 * total
+    }
+
+    pub fn noise3d(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.noise3d_grad(x, y, z).0
+    }
 
-        let t2 = 0.5 - x2*x2 - y2*y2;
-        if t2 > 0.0 {
-            let t2_2 = t2 * t2;
-            n2 = t2_2 * t2_2 * Self::grad(gi2, x2, y2);
+    // Valor y gradiente analítico: d/dp [t⁴ (g·d)] = t⁴ g − 8 t³ (g·d) d
+    pub fn noise3d_grad(&self, x: f32, y: f32, z: f32) -> (f32, Vec3A) {
+        let s = (x + y + z) * F3;
+        let i = (x + s).floor() as i32;
+        let j = (y + s).floor() as i32;
+        let k = (z + s).floor() as i32;
+
+        let t = (i + j + k) as f32 * G3;
+        let x0 = x - (i as f32 - t);
+        let y0 = y - (j as f32 - t);
+        let z0 = z - (k as f32 - t);
+
+        // Orden de las coordenadas para saber qué tetraedro del cubo contiene al punto
+        let (i1, j1, k1, i2, j2, k2) = if x0 >= y0 {
+            if y0 >= z0 {
+                (1, 0, 0, 1, 1, 0)
+            } else if x0 >= z0 {
+                (1, 0, 0, 1, 0, 1)
+            } else {
+                (0, 0, 1, 1, 0, 1)
+            }
+        } else if y0 < z0 {
+            (0, 0, 1, 0, 1, 1)
+        } else if x0 < z0 {
+            (0, 1, 0, 0, 1, 1)
+        } else {
+            (0, 1, 0, 1, 1, 0)
+        };
+
+        let corners = [
+            (x0, y0, z0, self.perm.hash3(i, j, k)),
+            (
+                x0 - i1 as f32 + G3,
+                y0 - j1 as f32 + G3,
+                z0 - k1 as f32 + G3,
+                self.perm.hash3(i + i1, j + j1, k + k1),
+            ),
+            (
+                x0 - i2 as f32 + 2.0 * G3,
+                y0 - j2 as f32 + 2.0 * G3,
+                z0 - k2 as f32 + 2.0 * G3,
+                self.perm.hash3(i + i2, j + j2, k + k2),
+            ),
+            (
+                x0 - 1.0 + 3.0 * G3,
+                y0 - 1.0 + 3.0 * G3,
+                z0 - 1.0 + 3.0 * G3,
+                self.perm.hash3(i + 1, j + 1, k + 1),
+            ),
+        ];
+
+        let mut total = 0.0;
+        let mut gradient = Vec3A::ZERO;
+        for (cx, cy, cz, hash) in corners {
+            let t = 0.6 - cx * cx - cy * cy - cz * cz;
+            if t > 0.0 {
+                let g = GRAD3[hash % 12];
+                let g = Vec3A::new(g[0], g[1], g[2]);
+                let d = Vec3A::new(cx, cy, cz);
+                let dot = g.dot(d);
+                let t2 = t * t;
+                total += t2 * t2 * dot;
+                gradient += g * (t2 * t2) - d * (8.0 * t2 * t * dot);
+            }
         }
 
-        70.0 * (n0 + n1 + n2)
+        (32.0 * total, gradient * 32.0)
     }
 
-    pub fn fbm(&self, x: f32, y: f32, octaves: usize, lacunarity: f32, gain: f32) -> f32 {
+    // La cuarta coordenada suele ser el tiempo: el patrón evoluciona sin desplazarse
+    pub fn noise4d(&self, x: f32, y: f32, z: f32, w: f32) -> f32 {
+        let s = (x + y + z + w) * F4;
+        let i = (x + s).floor() as i32;
+        let j = (y + s).floor() as i32;
+        let k = (z + s).floor() as i32;
+        let l = (w + s).floor() as i32;
+
+        let t = (i + j + k + l) as f32 * G4;
+        let offset0 = [x - (i as f32 - t), y - (j as f32 - t), z - (k as f32 - t), w - (l as f32 - t)];
+
+        // Rango de cada coordenada: cuántas de las otras son menores que ella
+        let mut rank = [0; 4];
+        for a in 0..4 {
+            for b in (a + 1)..4 {
+                if offset0[a] > offset0[b] {
+                    rank[a] += 1;
+                } else {
+                    rank[b] += 1;
+                }
+            }
+        }
+
+        let base = [i, j, k, l];
         let mut total = 0.0;
-        let mut frequency = 1.0;
-        let mut amplitude = 1.0;
-        let mut max_value = 0.0;
-
-        for _ in 0..octaves {
-            total += self.noise2d(x * frequency, y * frequency) * amplitude;
-            max_value += amplitude;
-            amplitude *= gain;
-            frequency *= lacunarity;
+
+        // Vértices del simplex: en el paso n valen 1 las coordenadas con rango >= 4 - n
+        for step in 0..5 {
+            let mut corner = [0i32; 4];
+            let mut offset = [0.0f32; 4];
+            for axis in 0..4 {
+                corner[axis] = (rank[axis] + step >= 4) as i32;
+                offset[axis] = offset0[axis] - corner[axis] as f32 + step as f32 * G4;
+            }
+
+            let t = 0.6 - offset.iter().map(|c| c * c).sum::<f32>();
+            if t > 0.0 {
+                let hash = self.perm.hash4(
+                    base[0] + corner[0],
+                    base[1] + corner[1],
+                    base[2] + corner[2],
+                    base[3] + corner[3],
+                );
+                let g = GRAD4[hash % 32];
+                let t2 = t * t;
+                total += t2 * t2 * (g[0] * offset[0] + g[1] * offset[1] + g[2] * offset[2] + g[3] * offset[3]);
+            }
         }
 
-        total / max_value
+        27.0 * total
     }
+
+    #[allow(dead_code)]
+    pub fn fbm(&self, x: f32, y: f32, fractal: Fractal) -> f32 {
+        fbm_with(fractal, |frequency| self.noise2d(x * frequency, y * frequency))
+    }
+
+    pub fn fbm3(&self, p: Vec3A, fractal: Fractal) -> f32 {
+        fbm_with(fractal, |frequency| {
+            let p = p * frequency;
+            self.noise3d(p.x, p.y, p.z)
+        })
+    }
+
+    // Solo las coordenadas espaciales escalan con la frecuencia; `w` anima todas las octavas
+    pub fn fbm4(&self, p: Vec3A, w: f32, fractal: Fractal) -> f32 {
+        fbm_with(fractal, |frequency| {
+            let p = p * frequency;
+            self.noise4d(p.x, p.y, p.z, w)
+        })
+    }
+}
+
+fn fbm_with(fractal: Fractal, noise: impl Fn(f32) -> f32) -> f32 {
+    let mut total = 0.0;
+    let amplitude = fractal.octaves(|frequency, amplitude| {
+        total += noise(frequency) * amplitude;
+    });
+    total / amplitude
 }
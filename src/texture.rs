@@ -0,0 +1,285 @@
+use crate::fragment::Fragment;
+use crate::varyings::Varying;
+use glam::{Vec2, Vec3A};
+use raylib::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WrapMode {
+    #[default]
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+impl WrapMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "repeat" => Some(WrapMode::Repeat),
+            "clamp" => Some(WrapMode::Clamp),
+            "mirror" => Some(WrapMode::Mirror),
+            _ => None,
+        }
+    }
+
+    fn apply(self, coord: i32, size: i32) -> i32 {
+        match self {
+            WrapMode::Repeat => coord.rem_euclid(size),
+            WrapMode::Clamp => coord.clamp(0, size - 1),
+            WrapMode::Mirror => {
+                let period = coord.rem_euclid(2 * size);
+                if period >= size { 2 * size - 1 - period } else { period }
+            }
+        }
+    }
+}
+
+// Nearest y Bilinear usan el nivel de mipmap más cercano; Trilinear mezcla los dos vecinos
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterMode {
+    Nearest,
+    Bilinear,
+    #[default]
+    Trilinear,
+}
+
+impl FilterMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "nearest" => Some(FilterMode::Nearest),
+            "bilinear" => Some(FilterMode::Bilinear),
+            "trilinear" => Some(FilterMode::Trilinear),
+            _ => None,
+        }
+    }
+}
+
+struct MipLevel {
+    width: i32,
+    height: i32,
+    texels: Vec<Vec3A>,
+}
+
+impl MipLevel {
+    fn texel(&self, x: i32, y: i32, wrap: WrapMode) -> Vec3A {
+        let x = wrap.apply(x, self.width);
+        let y = wrap.apply(y, self.height);
+        self.texels[(y * self.width + x) as usize]
+    }
+
+    // Promedio 2x2; en tamaños impares el último texel se repite
+    fn downsample(&self) -> MipLevel {
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+        let mut texels = Vec::with_capacity((width * height) as usize);
+
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = Vec3A::ZERO;
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    sum += self.texel(x * 2 + dx, y * 2 + dy, WrapMode::Clamp);
+                }
+                texels.push(sum * 0.25);
+            }
+        }
+
+        MipLevel { width, height, texels }
+    }
+}
+
+pub struct Texture {
+    levels: Vec<MipLevel>,
+    pub wrap: WrapMode,
+    pub filter: FilterMode,
+}
+
+impl Texture {
+    // Carga PNG/JPEG con raylib; v = 0 corresponde a la fila inferior de la imagen
+    pub fn load(path: &str) -> Result<Self, String> {
+        let image = Image::load_image(path).map_err(|err| format!("No se pudo cargar la textura {path}: {err}"))?;
+        let width = image.width();
+        let height = image.height();
+        let texels = image
+            .get_image_data()
+            .iter()
+            .map(|color| Vec3A::new(color.r as f32, color.g as f32, color.b as f32) / 255.0)
+            .collect();
+
+        Ok(Texture::from_texels(width, height, texels))
+    }
+
+    pub fn from_texels(width: i32, height: i32, texels: Vec<Vec3A>) -> Self {
+        assert_eq!(texels.len(), (width * height) as usize, "tamaño de textura inválido");
+        Texture {
+            levels: vec![MipLevel { width, height, texels }],
+            wrap: WrapMode::default(),
+            filter: FilterMode::default(),
+        }
+    }
+
+    pub fn with_wrap(mut self, wrap: WrapMode) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: FilterMode) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn with_mipmaps(mut self) -> Self {
+        self.generate_mipmaps();
+        self
+    }
+
+    pub fn generate_mipmaps(&mut self) {
+        self.levels.truncate(1);
+        loop {
+            let last = &self.levels[self.levels.len() - 1];
+            if last.width == 1 && last.height == 1 {
+                break;
+            }
+            let next = last.downsample();
+            self.levels.push(next);
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.levels[0].width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.levels[0].height
+    }
+
+    // Elige el nivel de detalle a partir de las derivadas de la UV en pantalla
+    pub fn sample_grad(&self, uv: Vec2, ddx: Vec2, ddy: Vec2) -> Vec3A {
+        self.sample_level(uv, self.lod(ddx, ddy))
+    }
+
+    pub fn sample_varying(&self, fragment: &Fragment, varying: Varying) -> Vec3A {
+        let (ddx, ddy) = fragment.derivatives(varying);
+        self.sample_grad(fragment.varyings.vec2(varying), ddx, ddy)
+    }
+
+    pub fn sample_level(&self, uv: Vec2, lod: f32) -> Vec3A {
+        let max_level = (self.levels.len() - 1) as f32;
+        let lod = lod.clamp(0.0, max_level);
+
+        match self.filter {
+            FilterMode::Nearest => self.sample_nearest(&self.levels[lod.round() as usize], uv),
+            FilterMode::Bilinear => self.sample_bilinear(&self.levels[lod.round() as usize], uv),
+            FilterMode::Trilinear => {
+                let lower = lod.floor();
+                let t = lod - lower;
+                let a = self.sample_bilinear(&self.levels[lower as usize], uv);
+                if t <= 0.0 {
+                    return a;
+                }
+                let b = self.sample_bilinear(&self.levels[lower as usize + 1], uv);
+                a + (b - a) * t
+            }
+        }
+    }
+
+    fn lod(&self, ddx: Vec2, ddy: Vec2) -> f32 {
+        let size = Vec2::new(self.width() as f32, self.height() as f32);
+        let dx = Vec2::new(ddx.x * size.x, ddx.y * size.y);
+        let dy = Vec2::new(ddy.x * size.x, ddy.y * size.y);
+        let rho = dx.length().max(dy.length());
+        if rho > 0.0 { rho.log2() } else { 0.0 }
+    }
+
+    fn sample_nearest(&self, level: &MipLevel, uv: Vec2) -> Vec3A {
+        let x = (uv.x * level.width as f32).floor() as i32;
+        let y = ((1.0 - uv.y) * level.height as f32).floor() as i32;
+        level.texel(x, y, self.wrap)
+    }
+
+    fn sample_bilinear(&self, level: &MipLevel, uv: Vec2) -> Vec3A {
+        let x = uv.x * level.width as f32 - 0.5;
+        let y = (1.0 - uv.y) * level.height as f32 - 0.5;
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let tx = x - x0;
+        let ty = y - y0;
+        let (x0, y0) = (x0 as i32, y0 as i32);
+
+        let top = self.lerp_texels(level, x0, y0, tx);
+        let bottom = self.lerp_texels(level, x0, y0 + 1, tx);
+        top + (bottom - top) * ty
+    }
+
+    fn lerp_texels(&self, level: &MipLevel, x: i32, y: i32, t: f32) -> Vec3A {
+        let a = level.texel(x, y, self.wrap);
+        let b = level.texel(x + 1, y, self.wrap);
+        a + (b - a) * t
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 4x1: negro, rojo, verde, azul
+    fn strip() -> Texture {
+        let texels = vec![
+            Vec3A::ZERO,
+            Vec3A::new(1.0, 0.0, 0.0),
+            Vec3A::new(0.0, 1.0, 0.0),
+            Vec3A::new(0.0, 0.0, 1.0),
+        ];
+        Texture::from_texels(4, 1, texels)
+    }
+
+    #[test]
+    fn wrap_modes() {
+        assert_eq!(WrapMode::Repeat.apply(5, 4), 1);
+        assert_eq!(WrapMode::Repeat.apply(-1, 4), 3);
+        assert_eq!(WrapMode::Clamp.apply(5, 4), 3);
+        assert_eq!(WrapMode::Clamp.apply(-1, 4), 0);
+        // Espejo: 4 5 6 7 → 3 2 1 0 y -1 → 0
+        assert_eq!(WrapMode::Mirror.apply(4, 4), 3);
+        assert_eq!(WrapMode::Mirror.apply(7, 4), 0);
+        assert_eq!(WrapMode::Mirror.apply(-1, 4), 0);
+        assert_eq!(WrapMode::Mirror.apply(-5, 4), 3);
+    }
+
+    #[test]
+    fn nearest_and_bilinear_filtering() {
+        let nearest = strip().with_filter(FilterMode::Nearest);
+        assert_eq!(nearest.sample_level(Vec2::new(0.3, 0.5), 0.0), Vec3A::new(1.0, 0.0, 0.0));
+
+        // Entre los centros del texel 1 (u = 0.375) y el 2 (u = 0.625)
+        let bilinear = strip().with_filter(FilterMode::Bilinear);
+        let color = bilinear.sample_level(Vec2::new(0.5, 0.5), 0.0);
+        assert!((color - Vec3A::new(0.5, 0.5, 0.0)).length() < 1e-5);
+
+        // Con Repeat el borde izquierdo mezcla con el último texel; con Clamp no
+        let left = Vec2::new(0.0, 0.5);
+        let repeat = bilinear.sample_level(left, 0.0);
+        let clamp = strip().with_filter(FilterMode::Bilinear).with_wrap(WrapMode::Clamp).sample_level(left, 0.0);
+        assert!((repeat - Vec3A::new(0.0, 0.0, 0.5)).length() < 1e-5);
+        assert_eq!(clamp, Vec3A::ZERO);
+    }
+
+    #[test]
+    fn mipmaps_average_down_to_one_texel() {
+        let texture = strip().with_mipmaps();
+        assert_eq!(texture.levels.len(), 3);
+        let average = Vec3A::new(0.25, 0.25, 0.25);
+        assert!((texture.sample_level(Vec2::new(0.5, 0.5), 10.0) - average).length() < 1e-5);
+
+        // Trilinear mezcla los dos niveles vecinos
+        let between = texture.sample_level(Vec2::new(0.5, 0.5), 1.5);
+        let level1 = texture.with_filter(FilterMode::Bilinear).sample_level(Vec2::new(0.5, 0.5), 1.0);
+        assert!((between - (level1 + average) * 0.5).length() < 1e-5);
+    }
+
+    #[test]
+    fn lod_follows_the_screen_derivatives() {
+        let texture = Texture::from_texels(64, 64, vec![Vec3A::ZERO; 64 * 64]);
+        // Un texel por píxel es el nivel 0; cuatro por píxel, el 2
+        assert!((texture.lod(Vec2::new(1.0 / 64.0, 0.0), Vec2::new(0.0, 1.0 / 64.0))).abs() < 1e-5);
+        assert!((texture.lod(Vec2::new(4.0 / 64.0, 0.0), Vec2::ZERO) - 2.0).abs() < 1e-5);
+    }
+}
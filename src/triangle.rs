@@ -1,106 +1,79 @@
 use crate::fragment::Fragment;
 use crate::vertex::Vertex;
-use crate::Light;
-use raylib::prelude::*;
+use crate::varyings::Varyings;
+use glam::{Mat3A, Vec3A};
+
+// Los tres pesos baricéntricos son afines en pantalla: w = Z + dx·(x - c.x) + dy·(y - c.y),
+// con Z = (0, 0, 1) el valor en el vértice c. Se evalúan juntos en un Vec3A.
+struct Barycentric {
+    origin: Vec3A,
+    dx: Vec3A,
+    dy: Vec3A,
+}
 
-fn barycentric_coordinates(p_x: f32, p_y: f32, a: &Vertex, b: &Vertex, c: &Vertex) -> (f32, f32, f32) {
-    let a_x = a.transformed_position.x;
-    let b_x = b.transformed_position.x;
-    let c_x = c.transformed_position.x;
-    let a_y = a.transformed_position.y;
-    let b_y = b.transformed_position.y;
-    let c_y = c.transformed_position.y;
+impl Barycentric {
+    fn new(a: Vec3A, b: Vec3A, c: Vec3A) -> Option<Self> {
+        let area = (b.y - c.y) * (a.x - c.x) + (c.x - b.x) * (a.y - c.y);
+        if area.abs() < 1e-10 {
+            return None;
+        }
 
-    let area = (b_y - c_y) * (a_x - c_x) + (c_x - b_x) * (a_y - c_y);
+        let dw1_dx = (b.y - c.y) / area;
+        let dw2_dx = (c.y - a.y) / area;
+        let dw1_dy = (c.x - b.x) / area;
+        let dw2_dy = (a.x - c.x) / area;
 
-    if area.abs() < 1e-10 {
-        return (-1.0, -1.0, -1.0);
+        Some(Barycentric {
+            origin: c,
+            dx: Vec3A::new(dw1_dx, dw2_dx, -dw1_dx - dw2_dx),
+            dy: Vec3A::new(dw1_dy, dw2_dy, -dw1_dy - dw2_dy),
+        })
     }
 
-    let w1 = ((b_y - c_y) * (p_x - c_x) + (c_x - b_x) * (p_y - c_y)) / area;
-    let w2 = ((c_y - a_y) * (p_x - c_x) + (a_x - c_x) * (p_y - c_y)) / area;
-    let w3 = 1.0 - w1 - w2;
-
-    (w1, w2, w3)
+    fn at(&self, x: f32, y: f32) -> Vec3A {
+        Vec3A::Z + self.dx * (x - self.origin.x) + self.dy * (y - self.origin.y)
+    }
 }
 
-pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, light: &Light) -> Vec<Fragment> {
+pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragment> {
     let mut fragments = Vec::new();
-
-    let min_x = v1.transformed_position.x.min(v2.transformed_position.x).min(v3.transformed_position.x).floor() as i32;
-    let max_x = v1.transformed_position.x.max(v2.transformed_position.x).max(v3.transformed_position.x).ceil() as i32;
-    let min_y = v1.transformed_position.y.min(v2.transformed_position.y).min(v3.transformed_position.y).floor() as i32;
-    let max_y = v1.transformed_position.y.max(v2.transformed_position.y).max(v3.transformed_position.y).ceil() as i32;
-
-    for y in min_y..=max_y {
-        for x in min_x..=max_x {
+    let (a, b, c) = (v1.transformed_position, v2.transformed_position, v3.transformed_position);
+    let Some(weights) = Barycentric::new(a, b, c) else {
+        return fragments;
+    };
+
+    // Como los pesos son afines, las derivadas de los interpolantes son constantes en todo
+    // el triángulo
+    let ddx = Varyings::interpolate(&v1.varyings, &v2.varyings, &v3.varyings, weights.dx.x, weights.dx.y, weights.dx.z);
+    let ddy = Varyings::interpolate(&v1.varyings, &v2.varyings, &v3.varyings, weights.dy.x, weights.dy.y, weights.dy.z);
+
+    // Con los atributos de los vértices como columnas, interpolar es un producto matriz-vector
+    let normals = Mat3A::from_cols(v1.transformed_normal, v2.transformed_normal, v3.transformed_normal);
+    let world_positions = Mat3A::from_cols(v1.world_position, v2.world_position, v3.world_position);
+    let depths = Vec3A::new(a.z, b.z, c.z);
+
+    let min = a.min(b).min(c).floor();
+    let max = a.max(b).max(c).ceil();
+
+    for y in min.y as i32..=max.y as i32 {
+        for x in min.x as i32..=max.x as i32 {
             let p_x = x as f32 + 0.5;
             let p_y = y as f32 + 0.5;
 
-            let (w1, w2, w3) = barycentric_coordinates(p_x, p_y, v1, v2, v3);
-
-            if w1 >= 0.0 && w2 >= 0.0 && w3 >= 0.0 {
-                let interpolated_normal = Vector3::new(
-                    w1 * v1.transformed_normal.x + w2 * v2.transformed_normal.x + w3 * v3.transformed_normal.x,
-                    w1 * v1.transformed_normal.y + w2 * v2.transformed_normal.y + w3 * v3.transformed_normal.y,
-                    w1 * v1.transformed_normal.z + w2 * v2.transformed_normal.z + w3 * v3.transformed_normal.z,
-                );
-
-                let normal_length = (interpolated_normal.x * interpolated_normal.x
-                    + interpolated_normal.y * interpolated_normal.y
-                    + interpolated_normal.z * interpolated_normal.z).sqrt();
-                let mut normalized_normal = interpolated_normal;
-                if normal_length > 0.0 {
-                    normalized_normal.x /= normal_length;
-                    normalized_normal.y /= normal_length;
-                    normalized_normal.z /= normal_length;
-                }
-
-                let world_pos = Vector3::new(
-                    w1 * v1.world_position.x + w2 * v2.world_position.x + w3 * v3.world_position.x,
-                    w1 * v1.world_position.y + w2 * v2.world_position.y + w3 * v3.world_position.y,
-                    w1 * v1.world_position.z + w2 * v2.world_position.z + w3 * v3.world_position.z,
-                );
-
-                let interpolated_color = Vector3::new(
-                    w1 * v1.color.x + w2 * v2.color.x + w3 * v3.color.x,
-                    w1 * v1.color.y + w2 * v2.color.y + w3 * v3.color.y,
-                    w1 * v1.color.z + w2 * v2.color.z + w3 * v3.color.z, 
-                );
-
-                // Calcular iluminación
-                let mut light_dir = Vector3::new(
-                    light.position.x - world_pos.x,
-                    light.position.y - world_pos.y,
-                    light.position.z - world_pos.z,
-                );
-
-                let light_length = (light_dir.x * light_dir.x + light_dir.y * light_dir.y + light_dir.z * light_dir.z).sqrt();
-                if light_length > 0.0 {
-                    light_dir.x /= light_length;
-                    light_dir.y /= light_length;
-                    light_dir.z /= light_length;
-                }
-
-                let diffuse = (normalized_normal.x * light_dir.x
-                    + normalized_normal.y * light_dir.y
-                    + normalized_normal.z * light_dir.z).max(0.0);
-
-                let wrap = 0.4;
-                let wrapped_diffuse = (diffuse + wrap) / (1.0 + wrap);
-
-                let ambient = 0.2;
-                let intensity = ambient + wrapped_diffuse * (1.0 - ambient);
-
-                // Solo .x es iluminación; .y/.z son UVs (se usarán en el shader)
-                let final_color = Vector3::new(intensity, interpolated_color.y, interpolated_color.z);
+            let w = weights.at(p_x, p_y);
+            if !w.cmpge(Vec3A::ZERO).all() {
+                continue;
+            }
 
-                let depth = w1 * v1.transformed_position.z
-                    + w2 * v2.transformed_position.z
-                    + w3 * v3.transformed_position.z;
+            let normal = (normals * w).normalize_or_zero();
+            let world_pos = world_positions * w;
+            let varyings = Varyings::interpolate(&v1.varyings, &v2.varyings, &v3.varyings, w.x, w.y, w.z);
+            let depth = depths.dot(w);
 
-                fragments.push(Fragment::new(p_x, p_y, world_pos, final_color, depth, normalized_normal));
-            }
+            fragments.push(
+                Fragment::new(p_x, p_y, world_pos, varyings, depth, normal)
+                    .with_derivatives(ddx, ddy),
+            );
         }
     }
 
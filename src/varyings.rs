@@ -0,0 +1,156 @@
+use glam::{Vec2, Vec3A, Vec4};
+
+pub const MAX_VARYINGS: usize = 19;
+pub const MAX_CUSTOM_VARYINGS: usize = 6;
+// Los slots se guardan de a cuatro para interpolarlos con SIMD
+const LANES: usize = MAX_VARYINGS.div_ceil(4);
+// Los custom ocupan los huecos que dejan los vec3 y después el último carril, así ningún
+// interpolante cruza de un carril a otro
+const CUSTOM_SLOTS: [usize; MAX_CUSTOM_VARYINGS] = [7, 11, 15, 16, 17, 18];
+
+// Interpolantes que el vertex shader emite y el rasterizador interpola
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Varying {
+    Uv,
+    SphereUv,
+    Color,
+    Tangent,
+    ObjectPosition,
+    Custom(usize),
+}
+
+impl Varying {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "uv" | "tex_coords" => Some(Varying::Uv),
+            "sphere_uv" => Some(Varying::SphereUv),
+            "color" => Some(Varying::Color),
+            "tangent" => Some(Varying::Tangent),
+            "object_position" => Some(Varying::ObjectPosition),
+            _ => {
+                let index = name.strip_prefix("custom")?.parse().ok()?;
+                (index < MAX_CUSTOM_VARYINGS).then_some(Varying::Custom(index))
+            }
+        }
+    }
+
+    // Primer slot y cantidad de componentes
+    pub fn slots(self) -> (usize, usize) {
+        match self {
+            Varying::Uv => (0, 2),
+            Varying::SphereUv => (2, 2),
+            Varying::Color => (4, 3),
+            Varying::Tangent => (8, 3),
+            Varying::ObjectPosition => (12, 3),
+            Varying::Custom(index) => {
+                assert!(index < MAX_CUSTOM_VARYINGS, "varying custom{index} fuera de rango");
+                (CUSTOM_SLOTS[index], 1)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Varyings {
+    lanes: [Vec4; LANES],
+}
+
+impl Varyings {
+    pub fn new() -> Self {
+        Varyings::default()
+    }
+
+    fn set(&mut self, index: usize, value: f32) {
+        self.lanes[index / 4][index % 4] = value;
+    }
+
+    pub fn set_float(&mut self, varying: Varying, value: f32) {
+        let (start, _) = varying.slots();
+        self.set(start, value);
+    }
+
+    pub fn set_vec2(&mut self, varying: Varying, value: Vec2) {
+        let (start, _) = varying.slots();
+        self.set(start, value.x);
+        self.set(start + 1, value.y);
+    }
+
+    pub fn set_vec3(&mut self, varying: Varying, value: Vec3A) {
+        let (start, _) = varying.slots();
+        self.set(start, value.x);
+        self.set(start + 1, value.y);
+        self.set(start + 2, value.z);
+    }
+
+    pub fn float(&self, varying: Varying) -> f32 {
+        let (start, _) = varying.slots();
+        self.slot(start)
+    }
+
+    pub fn vec2(&self, varying: Varying) -> Vec2 {
+        let (start, _) = varying.slots();
+        Vec2::new(self.slot(start), self.slot(start + 1))
+    }
+
+    pub fn vec3(&self, varying: Varying) -> Vec3A {
+        let (start, _) = varying.slots();
+        Vec3A::new(self.slot(start), self.slot(start + 1), self.slot(start + 2))
+    }
+
+    // Los componentes de un interpolante, sin copiar: siempre caen en un mismo carril
+    pub fn get(&self, varying: Varying) -> &[f32] {
+        let (start, len) = varying.slots();
+        let lane: &[f32; 4] = self.lanes[start / 4].as_ref();
+        &lane[start % 4..start % 4 + len]
+    }
+
+    pub fn slot(&self, index: usize) -> f32 {
+        self.lanes[index / 4][index % 4]
+    }
+
+    pub fn interpolate(a: &Varyings, b: &Varyings, c: &Varyings, w1: f32, w2: f32, w3: f32) -> Varyings {
+        let mut lanes = [Vec4::ZERO; LANES];
+        for (i, lane) in lanes.iter_mut().enumerate() {
+            *lane = a.lanes[i] * w1 + b.lanes[i] * w2 + c.lanes[i] * w3;
+        }
+        Varyings { lanes }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_varyings() -> impl Iterator<Item = Varying> {
+        [Varying::Uv, Varying::SphereUv, Varying::Color, Varying::Tangent, Varying::ObjectPosition]
+            .into_iter()
+            .chain((0..MAX_CUSTOM_VARYINGS).map(Varying::Custom))
+    }
+
+    #[test]
+    fn slots_stay_in_one_lane_and_do_not_overlap() {
+        let mut used = [false; LANES * 4];
+        for varying in all_varyings() {
+            let (start, len) = varying.slots();
+            assert_eq!(start / 4, (start + len - 1) / 4, "{varying:?} cruza de carril");
+            assert!(start + len <= MAX_VARYINGS);
+            for slot in &mut used[start..start + len] {
+                assert!(!*slot, "{varying:?} pisa otro interpolante");
+                *slot = true;
+            }
+        }
+    }
+
+    #[test]
+    fn get_returns_what_was_set() {
+        let mut varyings = Varyings::new();
+        varyings.set_vec3(Varying::Tangent, Vec3A::new(1.0, 2.0, 3.0));
+        varyings.set_vec2(Varying::Uv, Vec2::new(0.25, 0.75));
+        varyings.set_float(Varying::Custom(5), 9.0);
+
+        assert_eq!(varyings.get(Varying::Tangent), &[1.0, 2.0, 3.0]);
+        assert_eq!(varyings.get(Varying::Uv), &[0.25, 0.75]);
+        assert_eq!(varyings.get(Varying::Custom(5)), &[9.0]);
+        assert_eq!(varyings.get(Varying::Custom(0)), &[0.0]);
+    }
+}
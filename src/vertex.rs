@@ -1,43 +1,47 @@
 #![allow(dead_code)]
-use raylib::math::{Vector2, Vector3};
+use glam::{Vec2, Vec3A};
+use crate::varyings::Varyings;
 
 #[derive(Clone, Debug)]
 pub struct Vertex {
-  pub position: Vector3,
-  pub normal: Vector3,
-  pub tex_coords: Vector2,
-  pub color: Vector3,
-  pub transformed_position: Vector3,
-  pub transformed_normal: Vector3,
-  pub world_position: Vector3,
+  pub position: Vec3A,
+  pub normal: Vec3A,
+  pub tex_coords: Vec2,
+  pub color: Vec3A,
+  pub transformed_position: Vec3A,
+  pub transformed_normal: Vec3A,
+  pub world_position: Vec3A,
+  pub varyings: Varyings,
 }
 
 impl Vertex {
-  pub fn new(position: Vector3, normal: Vector3, tex_coords: Vector2) -> Self {
+  pub fn new(position: Vec3A, normal: Vec3A, tex_coords: Vec2) -> Self {
     Vertex {
       position,
       normal,
       tex_coords,
-      color: Vector3::new(0.0, 0.0, 0.0),
+      color: Vec3A::ONE,
       transformed_position: position,
       transformed_normal: normal,
-      world_position: Vector3::new(0.0, 0.0, 0.0),
+      world_position: Vec3A::ZERO,
+      varyings: Varyings::new(),
     }
   }
 
-  pub fn new_with_color(position: Vector3, color: Vector3) -> Self {
+  pub fn new_with_color(position: Vec3A, color: Vec3A) -> Self {
     Vertex {
       position,
-      normal: Vector3::new(0.0, 0.0, 0.0),
-      tex_coords: Vector2::new(0.0, 0.0),
+      normal: Vec3A::ZERO,
+      tex_coords: Vec2::ZERO,
       color,
-      transformed_position: Vector3::new(0.0, 0.0, 0.0),
-      transformed_normal: Vector3::new(0.0, 0.0, 0.0),
-      world_position: Vector3::new(0.0, 0.0, 0.0),
+      transformed_position: Vec3A::ZERO,
+      transformed_normal: Vec3A::ZERO,
+      world_position: Vec3A::ZERO,
+      varyings: Varyings::new(),
     }
   }
 
-  pub fn set_transformed(&mut self, position: Vector3, normal: Vector3) {
+  pub fn set_transformed(&mut self, position: Vec3A, normal: Vec3A) {
     self.transformed_position = position;
     self.transformed_normal = normal;
   }
@@ -46,13 +50,14 @@ impl Vertex {
 impl Default for Vertex {
   fn default() -> Self {
     Vertex {
-      position: Vector3::new(0.0, 0.0, 0.0),
-      normal: Vector3::new(0.0, 1.0, 0.0),
-      tex_coords: Vector2::new(0.0, 0.0),
-      color: Vector3::new(0.0, 0.0, 0.0),
-      transformed_position: Vector3::new(0.0, 0.0, 0.0),
-      transformed_normal: Vector3::new(0.0, 1.0, 0.0),
-      world_position: Vector3::zero()
+      position: Vec3A::ZERO,
+      normal: Vec3A::Y,
+      tex_coords: Vec2::ZERO,
+      color: Vec3A::ONE,
+      transformed_position: Vec3A::ZERO,
+      transformed_normal: Vec3A::Y,
+      world_position: Vec3A::ZERO,
+      varyings: Varyings::new(),
     }
   }
 }